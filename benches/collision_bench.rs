@@ -0,0 +1,84 @@
+use common::math::collision::CollisionShape;
+use common::math::Vec2;
+use common::spatial::SpatialGrid;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use ggez::graphics::Rect;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+const RADIUS: f32 = 16f32;
+
+/// Circles spread over an area that grows with their count, so density
+/// stays the same at every size.
+fn circles(count: usize) -> Vec<CollisionShape> {
+    let mut rng = StdRng::seed_from_u64(4300);
+    let side = (count as f32).sqrt() * RADIUS * 6f32;
+    (0..count)
+        .map(|_| CollisionShape::Circle {
+            center: Vec2::new(rng.gen_range(0f32..side), rng.gen_range(0f32..side)),
+            radius: RADIUS,
+        })
+        .collect()
+}
+
+fn bounds(shape: &CollisionShape) -> Rect {
+    let center = shape.center();
+    Rect::new(
+        center.x - RADIUS,
+        center.y - RADIUS,
+        RADIUS * 2f32,
+        RADIUS * 2f32,
+    )
+}
+
+fn brute_force(shapes: &[CollisionShape]) -> usize {
+    let mut count = 0;
+    for (i, a) in shapes.iter().enumerate() {
+        for b in &shapes[i + 1..] {
+            if a.intersects(b) {
+                count += 1;
+            }
+        }
+    }
+    count
+}
+
+fn grid(shapes: &[CollisionShape], grid: &mut SpatialGrid<usize>) -> usize {
+    grid.clear();
+    for (i, shape) in shapes.iter().enumerate() {
+        grid.insert(i, bounds(shape));
+    }
+    grid.overlapping_pairs()
+        .into_iter()
+        .filter(|&(a, b)| shapes[a].intersects(&shapes[b]))
+        .count()
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("overlapping pairs");
+    for count in [100, 1_000, 5_000] {
+        let shapes = circles(count);
+        let mut spatial = SpatialGrid::new(RADIUS * 4f32);
+        assert_eq!(grid(&shapes, &mut spatial), brute_force(&shapes));
+
+        group.bench_with_input(BenchmarkId::new("brute force", count), &shapes, |b, s| {
+            b.iter(|| brute_force(s))
+        });
+        group.bench_with_input(BenchmarkId::new("grid", count), &shapes, |b, s| {
+            b.iter(|| grid(s, &mut spatial))
+        });
+    }
+    group.finish();
+
+    c.bench_function("raycast through 5,000 colliders", |b| {
+        let shapes = circles(5_000);
+        let mut spatial = SpatialGrid::new(RADIUS * 4f32);
+        for (i, shape) in shapes.iter().enumerate() {
+            spatial.insert(i, bounds(shape));
+        }
+        b.iter(|| spatial.raycast(Vec2::zero(), Vec2::new(1f32, 0.7), 2_000f32))
+    });
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,132 @@
+use ggez::graphics::Rect;
+
+/// Point of a container something is pinned to: a corner, the middle of an
+/// edge or the center.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    #[default]
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Align {
+    Start,
+    Center,
+    End,
+}
+
+impl Anchor {
+    fn horizontal(&self) -> Align {
+        match self {
+            Anchor::TopLeft | Anchor::Left | Anchor::BottomLeft => Align::Start,
+            Anchor::Top | Anchor::Center | Anchor::Bottom => Align::Center,
+            Anchor::TopRight | Anchor::Right | Anchor::BottomRight => Align::End,
+        }
+    }
+
+    fn vertical(&self) -> Align {
+        match self {
+            Anchor::TopLeft | Anchor::Top | Anchor::TopRight => Align::Start,
+            Anchor::Left | Anchor::Center | Anchor::Right => Align::Center,
+            Anchor::BottomLeft | Anchor::Bottom | Anchor::BottomRight => Align::End,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Margin {
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+    pub left: f32,
+}
+
+impl Margin {
+    pub fn all(value: f32) -> Self {
+        Self {
+            top: value,
+            right: value,
+            bottom: value,
+            left: value,
+        }
+    }
+}
+
+/// Rect of a `size` box anchored in `container`, kept `margin` away from the
+/// edges it is anchored to.
+pub fn anchored(anchor: Anchor, margin: Margin, size: (f32, f32), container: Rect) -> Rect {
+    let (w, h) = size;
+    let x = match anchor.horizontal() {
+        Align::Start => container.x + margin.left,
+        Align::Center => container.x + (container.w - w + margin.left - margin.right) / 2f32,
+        Align::End => container.x + container.w - margin.right - w,
+    };
+    let y = match anchor.vertical() {
+        Align::Start => container.y + margin.top,
+        Align::Center => container.y + (container.h - h + margin.top - margin.bottom) / 2f32,
+        Align::End => container.y + container.h - margin.bottom - h,
+    };
+    Rect::new(x, y, w, h)
+}
+
+#[cfg(test)]
+mod tests {
+    use ggez::graphics::Rect;
+
+    use super::{anchored, Anchor, Margin};
+
+    const SCREEN: Rect = Rect::new(0f32, 0f32, 800f32, 600f32);
+
+    #[test]
+    fn test_anchored_every_anchor() {
+        let margin = Margin::all(10f32);
+        let size = (100f32, 50f32);
+        let expected = [
+            (Anchor::TopLeft, 10f32, 10f32),
+            (Anchor::Top, 350f32, 10f32),
+            (Anchor::TopRight, 690f32, 10f32),
+            (Anchor::Left, 10f32, 275f32),
+            (Anchor::Center, 350f32, 275f32),
+            (Anchor::Right, 690f32, 275f32),
+            (Anchor::BottomLeft, 10f32, 540f32),
+            (Anchor::Bottom, 350f32, 540f32),
+            (Anchor::BottomRight, 690f32, 540f32),
+        ];
+
+        for (anchor, x, y) in expected {
+            assert_eq!(
+                anchored(anchor, margin, size, SCREEN),
+                Rect::new(x, y, 100f32, 50f32),
+                "{:?}",
+                anchor
+            );
+        }
+    }
+
+    #[test]
+    fn test_anchored_uneven_margin() {
+        let margin = Margin {
+            top: 20f32,
+            left: 40f32,
+            ..Default::default()
+        };
+        let container = Rect::new(100f32, 100f32, 200f32, 100f32);
+
+        assert_eq!(
+            anchored(Anchor::Center, margin, (20f32, 20f32), container),
+            Rect::new(210f32, 150f32, 20f32, 20f32)
+        );
+        assert_eq!(
+            anchored(Anchor::BottomRight, margin, (20f32, 20f32), container),
+            Rect::new(280f32, 180f32, 20f32, 20f32)
+        );
+    }
+}
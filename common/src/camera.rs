@@ -0,0 +1,214 @@
+use ggez::graphics::{DrawParam, Rect};
+
+use crate::math::{Affine2, Vec2};
+use crate::time::GameTime;
+
+const MAX_SHAKE_OFFSET: f32 = 24f32;
+const MAX_SHAKE_ANGLE: f32 = 0.05;
+const TRAUMA_DECAY: f32 = 1.5;
+
+/// View into the world. `position` is the world point shown in the middle of
+/// the screen. Shake is only applied when drawing, so converting between
+/// screen and world stays stable while the camera shakes.
+#[derive(Clone, Debug)]
+pub struct Camera {
+    pub position: Vec2,
+    pub zoom: f32,
+    pub rotation: f32,
+    pub screen_size: Vec2,
+    trauma: f32,
+    shake_offset: Vec2,
+    shake_angle: f32,
+}
+
+impl Camera {
+    /// Camera that maps the world one to one onto a screen of `screen_size`.
+    pub fn new(screen_size: Vec2) -> Self {
+        Self {
+            position: screen_size / 2f32,
+            zoom: 1f32,
+            rotation: 0f32,
+            screen_size,
+            trauma: 0f32,
+            shake_offset: Vec2::zero(),
+            shake_angle: 0f32,
+        }
+    }
+
+    pub fn trauma(&self) -> f32 {
+        self.trauma
+    }
+
+    /// Trauma adds up to 1 and decays over time. The shake grows with its
+    /// square, so small hits barely move the screen.
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).clamp(0f32, 1f32);
+    }
+
+    /// Moves towards `target`, covering the same share of the distance per
+    /// second regardless of the frame rate.
+    pub fn follow(&mut self, target: Vec2, speed: f32, time: &GameTime) {
+        let t = 1f32 - (-speed * time.delta().as_secs_f32()).exp();
+        self.position = self.position.lerp(target, t);
+    }
+
+    /// Keeps the view inside `bounds`, centering on it when it is smaller
+    /// than the view. Rotation is ignored.
+    pub fn clamp_to(&mut self, bounds: Rect) {
+        let half = self.center() / self.zoom;
+        let clamp = |position: f32, start: f32, size: f32, half: f32| {
+            if size <= half * 2f32 {
+                start + size / 2f32
+            } else {
+                position.clamp(start + half, start + size - half)
+            }
+        };
+        self.position = Vec2::new(
+            clamp(self.position.x, bounds.x, bounds.w, half.x),
+            clamp(self.position.y, bounds.y, bounds.h, half.y),
+        );
+    }
+
+    pub fn update_shake(&mut self, time: &GameTime) {
+        self.trauma = (self.trauma - TRAUMA_DECAY * time.delta().as_secs_f32()).max(0f32);
+        let shake = self.trauma.powi(2);
+        // Cheap smooth noise, good enough to jitter the view.
+        let t = time.elapsed().as_secs_f32();
+        self.shake_offset =
+            Vec2::new((t * 37f32).sin(), (t * 53f32 + 1.3).sin()) * (MAX_SHAKE_OFFSET * shake);
+        self.shake_angle = (t * 41f32 + 2.1).sin() * MAX_SHAKE_ANGLE * shake;
+    }
+
+    fn center(&self) -> Vec2 {
+        self.screen_size / 2f32
+    }
+
+    pub fn world_to_screen(&self, point: Vec2) -> Vec2 {
+        ((point - self.position).rotated(-self.rotation) * self.zoom) + self.center()
+    }
+
+    pub fn screen_to_world(&self, point: Vec2) -> Vec2 {
+        ((point - self.center()) / self.zoom).rotated(self.rotation) + self.position
+    }
+
+    /// World to screen transform, including the shake.
+    fn matrix(&self) -> Affine2 {
+        Affine2::from_translation(self.center() + self.shake_offset)
+            * Affine2::from_angle(-(self.rotation + self.shake_angle))
+            * Affine2::from_scale(Vec2::new(self.zoom, self.zoom))
+            * Affine2::from_translation(-self.position)
+    }
+
+    /// Draws something placed by the world `transform` where the camera
+    /// shows it. ggez 0.7 has no transform stack, so every world draw goes
+    /// through here.
+    pub fn draw_param(&self, transform: Affine2) -> DrawParam {
+        DrawParam::new().transform((self.matrix() * transform).to_mat4())
+    }
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self::new(Vec2::zero())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use ggez::graphics::Rect;
+
+    use super::Camera;
+    use crate::math::Vec2;
+    use crate::time::GameTime;
+
+    fn assert_close(a: Vec2, b: Vec2) {
+        assert!(a.distance(b) < 1e-3, "{:?} != {:?}", a, b);
+    }
+
+    fn apply_matrix(camera: &Camera, point: Vec2) -> Vec2 {
+        camera.matrix().transform_point(point)
+    }
+
+    #[test]
+    fn test_default_view_is_identity() {
+        let camera = Camera::new(Vec2::new(800f32, 600f32));
+        let point = Vec2::new(120f32, 40f32);
+        assert_close(camera.world_to_screen(point), point);
+        assert_close(camera.screen_to_world(point), point);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut camera = Camera::new(Vec2::new(800f32, 600f32));
+        camera.position = Vec2::new(1000f32, -200f32);
+        camera.zoom = 2f32;
+        camera.rotation = 0.7;
+
+        let point = Vec2::new(1050f32, -180f32);
+        let screen = camera.world_to_screen(point);
+        assert_close(camera.screen_to_world(screen), point);
+        assert_close(apply_matrix(&camera, point), screen);
+        assert_close(
+            camera.world_to_screen(camera.position),
+            Vec2::new(400f32, 300f32),
+        );
+    }
+
+    #[test]
+    fn test_follow_is_frame_rate_independent() {
+        let target = Vec2::new(100f32, 0f32);
+        let mut coarse = Camera::new(Vec2::zero());
+        let mut fine = Camera::new(Vec2::zero());
+
+        let mut time = GameTime::default();
+        time.advance(Duration::from_millis(100));
+        coarse.follow(target, 5f32, &time);
+
+        let mut time = GameTime::default();
+        for _ in 0..10 {
+            time.advance(Duration::from_millis(10));
+            fine.follow(target, 5f32, &time);
+        }
+
+        assert_close(coarse.position, fine.position);
+        assert!(coarse.position.x > 0f32 && coarse.position.x < 100f32);
+    }
+
+    #[test]
+    fn test_clamp_to_bounds() {
+        let bounds = Rect::new(0f32, 0f32, 2000f32, 400f32);
+        let mut camera = Camera::new(Vec2::new(800f32, 600f32));
+
+        camera.position = Vec2::new(100f32, 100f32);
+        camera.clamp_to(bounds);
+        assert_close(camera.position, Vec2::new(400f32, 200f32));
+
+        camera.position = Vec2::new(1900f32, 100f32);
+        camera.zoom = 2f32;
+        camera.clamp_to(bounds);
+        assert_close(camera.position, Vec2::new(1800f32, 150f32));
+    }
+
+    #[test]
+    fn test_trauma_decays() {
+        let mut camera = Camera::new(Vec2::new(800f32, 600f32));
+        camera.add_trauma(0.8);
+        camera.add_trauma(0.8);
+        assert_eq!(camera.trauma(), 1f32);
+
+        let mut time = GameTime::default();
+        time.advance(Duration::from_secs(1));
+        camera.update_shake(&time);
+        assert!(camera.trauma() < 1f32);
+
+        time.advance(Duration::from_secs(1));
+        camera.update_shake(&time);
+        assert_eq!(camera.trauma(), 0f32);
+        assert_close(
+            apply_matrix(&camera, Vec2::new(400f32, 300f32)),
+            Vec2::new(400f32, 300f32),
+        );
+    }
+}
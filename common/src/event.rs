@@ -7,6 +7,9 @@ pub trait EventSender<Event> {
 
 pub trait EventReceiver<Event> {
     fn read(&mut self) -> Vec<Event>;
+
+    /// Events sent so far, left in place for other readers.
+    fn peek(&self) -> Vec<&Event>;
 }
 
 #[derive(Default, Debug)]
@@ -40,6 +43,13 @@ where
             .map(|e| *(e.downcast::<T>().unwrap()))
             .collect()
     }
+
+    fn peek(&self) -> Vec<&T> {
+        self.events
+            .get(&TypeId::of::<T>())
+            .map(|arr| arr.iter().map(|e| e.downcast_ref::<T>().unwrap()).collect())
+            .unwrap_or_default()
+    }
 }
 
 #[cfg(test)]
@@ -75,4 +85,17 @@ mod test {
         assert_eq!(read_events[0].0, "a".to_string());
         assert_eq!(read_events[1].0, "b".to_string());
     }
+
+    #[test]
+    fn test_peek_keeps_events() {
+        let mut system = EventSystem::default();
+        system.send(MyEvent("a".to_string()));
+
+        let peeked: Vec<&MyEvent> = system.peek();
+        assert_eq!(peeked.len(), 1);
+        let read_events: Vec<MyEvent> = system.read();
+        assert_eq!(read_events.len(), 1);
+        let peeked: Vec<&MyEvent> = system.peek();
+        assert!(peeked.is_empty());
+    }
 }
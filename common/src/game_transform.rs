@@ -4,17 +4,42 @@ use ecs::entity::Entity;
 use ecs::TypesQueryable;
 use ggez::{GameError, GameResult};
 
-use crate::math::Vec2;
+use crate::math::{Affine2, Vec2};
 
+/// Where an entity is, `rotation` in radians and a uniform `scale`.
 #[derive(Clone)]
 pub struct GameTransform {
     pub position: Vec2,
-    pub rotation: Vec2,
+    pub rotation: f32,
+    pub scale: f32,
 }
 
 impl GameTransform {
-    pub const fn new(position: Vec2, rotation: Vec2) -> Self {
-        Self { position, rotation }
+    pub const fn new(position: Vec2) -> Self {
+        Self {
+            position,
+            rotation: 0f32,
+            scale: 1f32,
+        }
+    }
+
+    pub fn with_rotation(mut self, rotation: f32) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Local to world transform.
+    pub fn matrix(&self) -> Affine2 {
+        Affine2::from_scale_angle_translation(
+            Vec2::new(self.scale, self.scale),
+            self.rotation,
+            self.position,
+        )
     }
 }
 
@@ -0,0 +1,516 @@
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::str::FromStr;
+
+use ggez::event::{Axis, Button};
+use ggez::input::keyboard::KeyCode;
+use ggez::input::mouse::MouseButton;
+use ggez::{Context, GameError, GameResult};
+
+use crate::math::Vec2;
+use crate::view::window_to_screen;
+
+const KEY_NAMES: &[(&str, KeyCode)] = &[
+    ("A", KeyCode::A),
+    ("B", KeyCode::B),
+    ("C", KeyCode::C),
+    ("D", KeyCode::D),
+    ("E", KeyCode::E),
+    ("F", KeyCode::F),
+    ("G", KeyCode::G),
+    ("H", KeyCode::H),
+    ("I", KeyCode::I),
+    ("J", KeyCode::J),
+    ("K", KeyCode::K),
+    ("L", KeyCode::L),
+    ("M", KeyCode::M),
+    ("N", KeyCode::N),
+    ("O", KeyCode::O),
+    ("P", KeyCode::P),
+    ("Q", KeyCode::Q),
+    ("R", KeyCode::R),
+    ("S", KeyCode::S),
+    ("T", KeyCode::T),
+    ("U", KeyCode::U),
+    ("V", KeyCode::V),
+    ("W", KeyCode::W),
+    ("X", KeyCode::X),
+    ("Y", KeyCode::Y),
+    ("Z", KeyCode::Z),
+    ("0", KeyCode::Key0),
+    ("1", KeyCode::Key1),
+    ("2", KeyCode::Key2),
+    ("3", KeyCode::Key3),
+    ("4", KeyCode::Key4),
+    ("5", KeyCode::Key5),
+    ("6", KeyCode::Key6),
+    ("7", KeyCode::Key7),
+    ("8", KeyCode::Key8),
+    ("9", KeyCode::Key9),
+    ("Up", KeyCode::Up),
+    ("Down", KeyCode::Down),
+    ("Left", KeyCode::Left),
+    ("Right", KeyCode::Right),
+    ("Space", KeyCode::Space),
+    ("Enter", KeyCode::Return),
+    ("Escape", KeyCode::Escape),
+    ("Tab", KeyCode::Tab),
+    ("Backspace", KeyCode::Back),
+    ("LShift", KeyCode::LShift),
+    ("RShift", KeyCode::RShift),
+    ("LControl", KeyCode::LControl),
+    ("RControl", KeyCode::RControl),
+    ("LAlt", KeyCode::LAlt),
+    ("RAlt", KeyCode::RAlt),
+];
+
+const MOUSE_NAMES: &[(&str, MouseButton)] = &[
+    ("MouseLeft", MouseButton::Left),
+    ("MouseRight", MouseButton::Right),
+    ("MouseMiddle", MouseButton::Middle),
+];
+
+const GAMEPAD_NAMES: &[(&str, Button)] = &[
+    ("PadSouth", Button::South),
+    ("PadEast", Button::East),
+    ("PadNorth", Button::North),
+    ("PadWest", Button::West),
+    ("PadLeftBumper", Button::LeftTrigger),
+    ("PadRightBumper", Button::RightTrigger),
+    ("PadLeftTrigger", Button::LeftTrigger2),
+    ("PadRightTrigger", Button::RightTrigger2),
+    ("PadSelect", Button::Select),
+    ("PadStart", Button::Start),
+];
+
+pub const DEFAULT_DEAD_ZONE: f32 = 0.2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    Gamepad(Button),
+}
+
+impl Binding {
+    pub fn name(&self) -> Option<&'static str> {
+        match self {
+            Binding::Key(key) => KEY_NAMES.iter().find(|(_, k)| k == key).map(|(n, _)| *n),
+            Binding::Mouse(button) => MOUSE_NAMES
+                .iter()
+                .find(|(_, b)| b == button)
+                .map(|(n, _)| *n),
+            Binding::Gamepad(button) => GAMEPAD_NAMES
+                .iter()
+                .find(|(_, b)| b == button)
+                .map(|(n, _)| *n),
+        }
+    }
+}
+
+impl FromStr for Binding {
+    type Err = GameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim();
+        if let Some((_, key)) = KEY_NAMES.iter().find(|(n, _)| *n == name) {
+            Ok(Binding::Key(*key))
+        } else if let Some((_, button)) = MOUSE_NAMES.iter().find(|(n, _)| *n == name) {
+            Ok(Binding::Mouse(*button))
+        } else if let Some((_, button)) = GAMEPAD_NAMES.iter().find(|(n, _)| *n == name) {
+            Ok(Binding::Gamepad(*button))
+        } else {
+            Err(GameError::CustomError(format!("Unknown binding {}", name)))
+        }
+    }
+}
+
+/// Gamepad state accumulated from ggez gamepad events. Stick values are in
+/// screen space, so pushing a stick up gives a negative y.
+#[derive(Clone, Debug, Default)]
+pub struct GamepadState {
+    pub left_stick: Vec2,
+    pub right_stick: Vec2,
+    pub buttons: HashSet<Button>,
+}
+
+impl GamepadState {
+    pub fn axis_changed(&mut self, axis: Axis, value: f32) {
+        match axis {
+            Axis::LeftStickX => self.left_stick.x = value,
+            Axis::LeftStickY => self.left_stick.y = -value,
+            Axis::RightStickX => self.right_stick.x = value,
+            Axis::RightStickY => self.right_stick.y = -value,
+            _ => {}
+        }
+    }
+
+    pub fn button_down(&mut self, button: Button) {
+        self.buttons.insert(button);
+    }
+
+    pub fn button_up(&mut self, button: Button) {
+        self.buttons.remove(&button);
+    }
+}
+
+/// Zeroes sticks inside the dead zone and rescales the rest so the output
+/// still covers the full 0..1 range.
+pub fn apply_dead_zone(stick: Vec2, dead_zone: f32) -> Vec2 {
+    let magnitude = stick.magnitude();
+    if magnitude <= dead_zone {
+        Vec2::zero()
+    } else {
+        let scaled = ((magnitude - dead_zone) / (1f32 - dead_zone)).min(1f32);
+        stick.normalized() * scaled
+    }
+}
+
+/// Raw input of a single frame.
+#[derive(Clone, Debug, Default)]
+pub struct InputState {
+    pub keys: HashSet<KeyCode>,
+    pub mouse_buttons: HashSet<MouseButton>,
+    pub mouse_position: Vec2,
+    pub gamepad: GamepadState,
+}
+
+impl InputState {
+    pub fn capture(ctx: &Context, gamepad: &GamepadState) -> Self {
+        let keys = ggez::input::keyboard::pressed_keys(ctx).clone();
+        let mouse_buttons = MOUSE_NAMES
+            .iter()
+            .map(|(_, button)| *button)
+            .filter(|button| ggez::input::mouse::button_pressed(ctx, *button))
+            .collect();
+
+        Self {
+            keys,
+            mouse_buttons,
+            mouse_position: window_to_screen(ctx, ggez::input::mouse::position(ctx).into()),
+            gamepad: gamepad.clone(),
+        }
+    }
+
+    pub fn is_pressed(&self, binding: &Binding) -> bool {
+        match binding {
+            Binding::Key(key) => self.keys.contains(key),
+            Binding::Mouse(button) => self.mouse_buttons.contains(button),
+            Binding::Gamepad(button) => self.gamepad.buttons.contains(button),
+        }
+    }
+
+    pub fn press(&mut self, binding: Binding) {
+        match binding {
+            Binding::Key(key) => self.keys.insert(key),
+            Binding::Mouse(button) => self.mouse_buttons.insert(button),
+            Binding::Gamepad(button) => self.gamepad.buttons.insert(button),
+        };
+    }
+
+    /// Everything held down this frame as bindings.
+    pub fn pressed(&self) -> Vec<Binding> {
+        self.keys
+            .iter()
+            .map(|k| Binding::Key(*k))
+            .chain(self.mouse_buttons.iter().map(|b| Binding::Mouse(*b)))
+            .chain(self.gamepad.buttons.iter().map(|b| Binding::Gamepad(*b)))
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ActionMap<A> {
+    bindings: HashMap<A, Vec<Binding>>,
+}
+
+impl<A> Default for ActionMap<A> {
+    fn default() -> Self {
+        Self {
+            bindings: Default::default(),
+        }
+    }
+}
+
+impl<A> ActionMap<A>
+where
+    A: Copy + Eq + Hash,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn bind(&mut self, action: A, binding: Binding) -> &mut Self {
+        let bindings = self.bindings.entry(action).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+        self
+    }
+
+    pub fn bindings(&self, action: A) -> &[Binding] {
+        self.bindings.get(&action).map_or(&[], |b| b.as_slice())
+    }
+
+    pub fn is_pressed(&self, action: A, state: &InputState) -> bool {
+        self.bindings(action).iter().any(|b| state.is_pressed(b))
+    }
+}
+
+impl<A> ActionMap<A>
+where
+    A: Copy + Eq + Hash + FromStr,
+{
+    /// Parses a toml table of `Action = ["Key", ...]` entries. Actions listed
+    /// in the config replace the bindings already in the map.
+    pub fn load_config(&mut self, config: &str) -> GameResult<()> {
+        let table: HashMap<String, Vec<String>> = toml::from_str(config)
+            .map_err(|e| GameError::CustomError(format!("Invalid bindings config: {}", e)))?;
+
+        for (name, bindings) in table {
+            let action = name
+                .parse::<A>()
+                .map_err(|_| GameError::CustomError(format!("Unknown action {}", name)))?;
+            self.bindings.remove(&action);
+            for binding in bindings {
+                self.bind(action, binding.parse()?);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Action states resolved from the last two frames of input.
+#[derive(Clone, Debug)]
+pub struct ActionState<A> {
+    map: ActionMap<A>,
+    current: InputState,
+    previous: InputState,
+    dead_zone: f32,
+}
+
+impl<A> Default for ActionState<A> {
+    fn default() -> Self {
+        Self::new(ActionMap::default())
+    }
+}
+
+impl<A> ActionState<A> {
+    pub fn new(map: ActionMap<A>) -> Self {
+        Self {
+            map,
+            current: Default::default(),
+            previous: Default::default(),
+            dead_zone: DEFAULT_DEAD_ZONE,
+        }
+    }
+
+    pub fn set_dead_zone(&mut self, dead_zone: f32) {
+        self.dead_zone = dead_zone;
+    }
+
+    pub fn map(&self) -> &ActionMap<A> {
+        &self.map
+    }
+
+    pub fn set_map(&mut self, map: ActionMap<A>) {
+        self.map = map;
+    }
+
+    pub fn update(&mut self, state: InputState) {
+        self.previous = std::mem::replace(&mut self.current, state);
+    }
+
+    pub fn raw(&self) -> &InputState {
+        &self.current
+    }
+
+    pub fn mouse_position(&self) -> Vec2 {
+        self.current.mouse_position
+    }
+
+    pub fn left_stick(&self) -> Vec2 {
+        apply_dead_zone(self.current.gamepad.left_stick, self.dead_zone)
+    }
+
+    pub fn right_stick(&self) -> Vec2 {
+        apply_dead_zone(self.current.gamepad.right_stick, self.dead_zone)
+    }
+}
+
+impl<A> ActionState<A>
+where
+    A: Copy + Eq + Hash,
+{
+    pub fn pressed(&self, action: A) -> bool {
+        self.map.is_pressed(action, &self.current)
+    }
+
+    pub fn just_pressed(&self, action: A) -> bool {
+        self.pressed(action) && !self.map.is_pressed(action, &self.previous)
+    }
+
+    pub fn just_released(&self, action: A) -> bool {
+        !self.pressed(action) && self.map.is_pressed(action, &self.previous)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use ggez::event::{Axis, Button};
+    use ggez::input::keyboard::KeyCode;
+    use ggez::input::mouse::MouseButton;
+    use ggez::GameError;
+
+    use super::{apply_dead_zone, ActionMap, ActionState, Binding, GamepadState, InputState};
+    use crate::math::Vec2;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    enum TestAction {
+        Jump,
+        Fire,
+    }
+
+    impl FromStr for TestAction {
+        type Err = GameError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s {
+                "Jump" => Ok(TestAction::Jump),
+                "Fire" => Ok(TestAction::Fire),
+                _ => Err(GameError::CustomError(s.to_string())),
+            }
+        }
+    }
+
+    fn state_with_keys(keys: &[KeyCode]) -> InputState {
+        InputState {
+            keys: keys.iter().copied().collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_binding() {
+        assert_eq!("W".parse::<Binding>().unwrap(), Binding::Key(KeyCode::W));
+        assert_eq!(
+            "MouseLeft".parse::<Binding>().unwrap(),
+            Binding::Mouse(MouseButton::Left)
+        );
+        assert_eq!(
+            "PadRightTrigger".parse::<Binding>().unwrap(),
+            Binding::Gamepad(Button::RightTrigger2)
+        );
+        assert!("Nope".parse::<Binding>().is_err());
+    }
+
+    #[test]
+    fn test_multiple_bindings() {
+        let mut map = ActionMap::new();
+        map.bind(TestAction::Jump, Binding::Key(KeyCode::W))
+            .bind(TestAction::Jump, Binding::Key(KeyCode::Up));
+
+        assert!(map.is_pressed(TestAction::Jump, &state_with_keys(&[KeyCode::W])));
+        assert!(map.is_pressed(TestAction::Jump, &state_with_keys(&[KeyCode::Up])));
+        assert!(!map.is_pressed(TestAction::Fire, &state_with_keys(&[KeyCode::Up])));
+    }
+
+    #[test]
+    fn test_load_config() {
+        let mut map = ActionMap::new();
+        map.bind(TestAction::Jump, Binding::Key(KeyCode::W));
+        map.load_config("Jump = [\"Space\"]\nFire = [\"MouseLeft\", \"LShift\"]")
+            .unwrap();
+
+        assert_eq!(
+            map.bindings(TestAction::Jump),
+            &[Binding::Key(KeyCode::Space)]
+        );
+        assert_eq!(
+            map.bindings(TestAction::Fire),
+            &[
+                Binding::Mouse(MouseButton::Left),
+                Binding::Key(KeyCode::LShift)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_load_config_unknown_action() {
+        let mut map = ActionMap::<TestAction>::new();
+        assert!(map.load_config("Dance = [\"W\"]").is_err());
+    }
+
+    #[test]
+    fn test_action_transitions() {
+        let mut map = ActionMap::new();
+        map.bind(TestAction::Jump, Binding::Key(KeyCode::Space));
+        let mut state = ActionState::new(map);
+
+        state.update(state_with_keys(&[KeyCode::Space]));
+        assert!(state.pressed(TestAction::Jump));
+        assert!(state.just_pressed(TestAction::Jump));
+
+        state.update(state_with_keys(&[KeyCode::Space]));
+        assert!(state.pressed(TestAction::Jump));
+        assert!(!state.just_pressed(TestAction::Jump));
+
+        state.update(state_with_keys(&[]));
+        assert!(!state.pressed(TestAction::Jump));
+        assert!(state.just_released(TestAction::Jump));
+
+        state.update(state_with_keys(&[]));
+        assert!(!state.just_released(TestAction::Jump));
+    }
+
+    #[test]
+    fn test_dead_zone() {
+        assert_eq!(apply_dead_zone(Vec2::new(0.1, 0.1), 0.2), Vec2::zero());
+
+        let full = apply_dead_zone(Vec2::new(1f32, 0f32), 0.2);
+        assert!((full.x - 1f32).abs() < f32::EPSILON);
+
+        let half = apply_dead_zone(Vec2::new(0f32, 0.6), 0.2);
+        assert!((half.y - 0.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_gamepad_state() {
+        let mut gamepad = GamepadState::default();
+        gamepad.axis_changed(Axis::LeftStickY, 1f32);
+        gamepad.axis_changed(Axis::RightStickX, -1f32);
+
+        let mut state = ActionState::<TestAction>::default();
+        state.update(InputState {
+            gamepad,
+            ..Default::default()
+        });
+
+        assert_eq!(state.left_stick(), Vec2::new(0f32, -1f32));
+        assert_eq!(state.right_stick(), Vec2::new(-1f32, 0f32));
+    }
+
+    #[test]
+    fn test_gamepad_button_binding() {
+        let mut map = ActionMap::new();
+        map.bind(TestAction::Fire, Binding::Gamepad(Button::RightTrigger2));
+        let mut state = ActionState::new(map);
+
+        let mut gamepad = GamepadState::default();
+        gamepad.button_down(Button::RightTrigger2);
+        state.update(InputState {
+            gamepad: gamepad.clone(),
+            ..Default::default()
+        });
+        assert!(state.just_pressed(TestAction::Fire));
+
+        gamepad.button_up(Button::RightTrigger2);
+        state.update(InputState {
+            gamepad,
+            ..Default::default()
+        });
+        assert!(state.just_released(TestAction::Fire));
+    }
+}
@@ -1,3 +1,11 @@
+pub mod anchor;
+pub mod camera;
 pub mod event;
 pub mod game_transform;
+pub mod input;
 pub mod math;
+pub mod physics;
+pub mod replay;
+pub mod spatial;
+pub mod time;
+pub mod view;
@@ -1,9 +1,9 @@
-use std::ops::{Add, Div, Mul, Sub};
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
 
 use ggez::graphics::DrawParam;
 use ggez::mint::Point2;
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub struct Vec2 {
     pub x: f32,
     pub y: f32,
@@ -18,6 +18,12 @@ impl Vec2 {
         Self { x, y }
     }
 
+    /// Unit vector pointing `angle` radians from the x axis.
+    pub fn from_angle(angle: f32) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        Self::new(cos, sin)
+    }
+
     pub fn magnitude_sq(&self) -> f32 {
         self.x.powi(2) + self.y.powi(2)
     }
@@ -40,12 +46,79 @@ impl Vec2 {
         }
     }
 
+    pub fn dot(&self, rhs: Self) -> f32 {
+        self.x * rhs.x + self.y * rhs.y
+    }
+
+    /// z of the 3D cross product, positive when `rhs` is clockwise on screen.
+    pub fn cross(&self, rhs: Self) -> f32 {
+        self.x * rhs.y - self.y * rhs.x
+    }
+
     pub fn abs(&self) -> Self {
         Self {
             x: self.x.abs(),
             y: self.y.abs(),
         }
     }
+
+    /// Angle from the x axis in radians, in `-PI..=PI`.
+    pub fn angle(&self) -> f32 {
+        self.y.atan2(self.x)
+    }
+
+    /// Signed angle that rotates `self` onto the direction of `rhs`.
+    pub fn angle_to(&self, rhs: Self) -> f32 {
+        self.cross(rhs).atan2(self.dot(rhs))
+    }
+
+    /// Rotated by `angle` radians, clockwise on screen since y points down.
+    pub fn rotated(&self, angle: f32) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        Self {
+            x: self.x * cos - self.y * sin,
+            y: self.x * sin + self.y * cos,
+        }
+    }
+
+    /// Rotated a quarter turn, clockwise on screen.
+    pub fn perpendicular(&self) -> Self {
+        Self::new(-self.y, self.x)
+    }
+
+    pub fn lerp(self, rhs: Self, t: f32) -> Self {
+        self + (rhs - self) * t
+    }
+
+    /// Mirrored off a surface with the unit `normal`.
+    pub fn reflect(&self, normal: Self) -> Self {
+        *self - normal * (2f32 * self.dot(normal))
+    }
+
+    /// Shortened to `max` when longer.
+    pub fn clamp_length(&self, max: f32) -> Self {
+        let length = self.magnitude();
+        if length > max {
+            *self * (max / length)
+        } else {
+            *self
+        }
+    }
+
+    /// The part of `self` along `rhs`.
+    pub fn project_onto(&self, rhs: Self) -> Self {
+        let length_sq = rhs.magnitude_sq();
+        if length_sq == 0f32 {
+            Self::zero()
+        } else {
+            rhs * (self.dot(rhs) / length_sq)
+        }
+    }
+
+    /// Whether both axes are within `epsilon` of each other.
+    pub fn approx_eq(&self, rhs: Self, epsilon: f32) -> bool {
+        (self.x - rhs.x).abs() <= epsilon && (self.y - rhs.y).abs() <= epsilon
+    }
 }
 
 impl From<Point2<f32>> for Vec2 {
@@ -81,36 +154,71 @@ impl Add for Vec2 {
     }
 }
 
-impl<T> Add<T> for Vec2
-where
-    T: Into<f32>,
-{
+impl AddAssign for Vec2 {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for Vec2 {
     type Output = Self;
 
-    fn add(self, rhs: T) -> Self::Output {
-        let num = rhs.into();
+    fn sub(self, rhs: Self) -> Self::Output {
         Self {
-            x: self.x + num,
-            y: self.y + num,
+            x: self.x - rhs.x,
+            y: self.y - rhs.y,
         }
     }
 }
 
-impl<T> Mul<T> for Vec2
-where
-    T: Into<f32>,
-{
+impl SubAssign for Vec2 {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl Neg for Vec2 {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Self::new(-self.x, -self.y)
+    }
+}
+
+impl Mul<f32> for Vec2 {
     type Output = Self;
 
-    fn mul(self, rhs: T) -> Self::Output {
-        let num = rhs.into();
+    fn mul(self, rhs: f32) -> Self::Output {
         Self {
-            x: self.x * num,
-            y: self.y * num,
+            x: self.x * rhs,
+            y: self.y * rhs,
         }
     }
 }
 
+impl Mul<Vec2> for f32 {
+    type Output = Vec2;
+
+    fn mul(self, rhs: Vec2) -> Self::Output {
+        rhs * self
+    }
+}
+
+/// Component-wise.
+impl Mul for Vec2 {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        Self::new(self.x * rhs.x, self.y * rhs.y)
+    }
+}
+
+impl MulAssign<f32> for Vec2 {
+    fn mul_assign(&mut self, rhs: f32) {
+        *self = *self * rhs;
+    }
+}
+
 impl Div<f32> for Vec2 {
     type Output = Self;
 
@@ -122,83 +230,1306 @@ impl Div<f32> for Vec2 {
     }
 }
 
-impl Sub for Vec2 {
-    type Output = Self;
+impl DivAssign<f32> for Vec2 {
+    fn div_assign(&mut self, rhs: f32) {
+        *self = *self / rhs;
+    }
+}
 
-    fn sub(self, rhs: Self) -> Self::Output {
+/// 2D affine transform, a linear part given by where it sends the x and y
+/// axes followed by a translation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Affine2 {
+    pub x_axis: Vec2,
+    pub y_axis: Vec2,
+    pub translation: Vec2,
+}
+
+impl Affine2 {
+    pub const IDENTITY: Self = Self {
+        x_axis: Vec2::new(1f32, 0f32),
+        y_axis: Vec2::new(0f32, 1f32),
+        translation: Vec2::zero(),
+    };
+
+    pub fn from_translation(translation: Vec2) -> Self {
         Self {
-            x: self.x - rhs.x,
-            y: self.y - rhs.y,
+            translation,
+            ..Self::IDENTITY
+        }
+    }
+
+    /// Rotation by `angle` radians, clockwise on screen like `Vec2::rotated`.
+    pub fn from_angle(angle: f32) -> Self {
+        let x_axis = Vec2::from_angle(angle);
+        Self {
+            x_axis,
+            y_axis: x_axis.perpendicular(),
+            translation: Vec2::zero(),
+        }
+    }
+
+    pub fn from_scale(scale: Vec2) -> Self {
+        Self {
+            x_axis: Vec2::new(scale.x, 0f32),
+            y_axis: Vec2::new(0f32, scale.y),
+            translation: Vec2::zero(),
+        }
+    }
+
+    /// Scales, then rotates, then translates.
+    pub fn from_scale_angle_translation(scale: Vec2, angle: f32, translation: Vec2) -> Self {
+        Self::from_translation(translation) * Self::from_angle(angle) * Self::from_scale(scale)
+    }
+
+    pub fn transform_point(&self, point: Vec2) -> Vec2 {
+        self.transform_vector(point) + self.translation
+    }
+
+    /// Applies the linear part only, leaving out the translation.
+    pub fn transform_vector(&self, vector: Vec2) -> Vec2 {
+        self.x_axis * vector.x + self.y_axis * vector.y
+    }
+
+    pub fn determinant(&self) -> f32 {
+        self.x_axis.cross(self.y_axis)
+    }
+
+    /// `None` when the transform flattens the plane, as a zero scale does.
+    pub fn inverse(&self) -> Option<Self> {
+        let det = self.determinant();
+        if det == 0f32 {
+            return None;
         }
+        let x_axis = Vec2::new(self.y_axis.y, -self.x_axis.y) / det;
+        let y_axis = Vec2::new(-self.y_axis.x, self.x_axis.x) / det;
+        let linear = Self {
+            x_axis,
+            y_axis,
+            translation: Vec2::zero(),
+        };
+        Some(Self {
+            translation: -linear.transform_vector(self.translation),
+            ..linear
+        })
+    }
+
+    /// Column-major 4x4 matrix, as ggez takes it.
+    pub fn to_mat4(&self) -> [[f32; 4]; 4] {
+        [
+            [self.x_axis.x, self.x_axis.y, 0f32, 0f32],
+            [self.y_axis.x, self.y_axis.y, 0f32, 0f32],
+            [0f32, 0f32, 1f32, 0f32],
+            [self.translation.x, self.translation.y, 0f32, 1f32],
+        ]
+    }
+}
+
+impl Default for Affine2 {
+    fn default() -> Self {
+        Self::IDENTITY
     }
 }
 
-impl Sub<f32> for Vec2 {
+/// `self * rhs` applies `rhs` first.
+impl Mul for Affine2 {
     type Output = Self;
 
-    fn sub(self, rhs: f32) -> Self::Output {
+    fn mul(self, rhs: Self) -> Self::Output {
         Self {
-            x: self.x - rhs,
-            y: self.y - rhs,
+            x_axis: self.transform_vector(rhs.x_axis),
+            y_axis: self.transform_vector(rhs.y_axis),
+            translation: self.transform_point(rhs.translation),
         }
     }
 }
 
 pub mod random {
-    use rand::Rng;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use std::collections::HashMap;
 
-    pub fn rand_element<T: Copy + Clone, const N: usize>(elements: [T; N]) -> T {
-        let idx = rand::thread_rng().gen_range(0..elements.len());
+    pub fn rand_element<T: Copy + Clone, R: Rng + ?Sized, const N: usize>(
+        rng: &mut R,
+        elements: [T; N],
+    ) -> T {
+        let idx = rng.gen_range(0..elements.len());
         elements[idx]
     }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    pub enum RandomStream {
+        Spawn,
+        Ai,
+        Particle,
+    }
+
+    impl RandomStream {
+        fn salt(self) -> u64 {
+            match self {
+                RandomStream::Spawn => 1,
+                RandomStream::Ai => 2,
+                RandomStream::Particle => 3,
+            }
+        }
+    }
+
+    /// Seeded random source. Every stream is derived from the same seed but
+    /// advances independently, so drawing from one never shifts the others.
+    pub struct GameRng {
+        seed: u64,
+        streams: HashMap<RandomStream, StdRng>,
+    }
+
+    impl GameRng {
+        pub fn new(seed: u64) -> Self {
+            Self {
+                seed,
+                streams: Default::default(),
+            }
+        }
+
+        pub fn seed(&self) -> u64 {
+            self.seed
+        }
+
+        pub fn stream(&mut self, stream: RandomStream) -> &mut StdRng {
+            let seed = self.seed;
+            self.streams.entry(stream).or_insert_with(|| {
+                let stream_seed = seed ^ stream.salt().wrapping_mul(0x9E37_79B9_7F4A_7C15);
+                StdRng::seed_from_u64(stream_seed)
+            })
+        }
+    }
+
+    impl Default for GameRng {
+        fn default() -> Self {
+            Self::new(rand::random())
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::{rand_element, GameRng, RandomStream};
+        use rand::Rng;
+
+        #[test]
+        fn test_same_seed_same_sequence() {
+            let mut a = GameRng::new(42);
+            let mut b = GameRng::new(42);
+
+            let a_values: Vec<u32> = (0..10)
+                .map(|_| a.stream(RandomStream::Spawn).gen())
+                .collect();
+            let b_values: Vec<u32> = (0..10)
+                .map(|_| b.stream(RandomStream::Spawn).gen())
+                .collect();
+
+            assert_eq!(a_values, b_values);
+        }
+
+        #[test]
+        fn test_streams_are_independent() {
+            let mut a = GameRng::new(7);
+            let mut b = GameRng::new(7);
+
+            for _ in 0..5 {
+                let _: u32 = b.stream(RandomStream::Ai).gen();
+            }
+
+            let a_value: u64 = a.stream(RandomStream::Spawn).gen();
+            let b_value: u64 = b.stream(RandomStream::Spawn).gen();
+            assert_eq!(a_value, b_value);
+        }
+
+        #[test]
+        fn test_streams_differ() {
+            let mut rng = GameRng::new(7);
+
+            let spawn: u64 = rng.stream(RandomStream::Spawn).gen();
+            let ai: u64 = rng.stream(RandomStream::Ai).gen();
+            assert_ne!(spawn, ai);
+        }
+
+        #[test]
+        fn test_rand_element() {
+            let mut rng = GameRng::new(1);
+            let element = rand_element(rng.stream(RandomStream::Spawn), [1, 2, 3]);
+            assert!([1, 2, 3].contains(&element));
+        }
+    }
 }
 
 pub mod collision {
     use super::Vec2;
 
-    pub struct BoxCollision {
-        pos: Vec2,
-        size: Vec2,
+    /// How two shapes overlap. `normal` points from the first shape to the
+    /// second, moving the second by `normal * depth` separates them.
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct Contact {
+        pub normal: Vec2,
+        pub depth: f32,
+        pub point: Vec2,
+    }
+
+    impl Contact {
+        /// The same contact seen from the second shape.
+        pub fn flipped(self) -> Self {
+            Self {
+                normal: -self.normal,
+                ..self
+            }
+        }
     }
 
-    impl BoxCollision {
-        pub fn new(pos: Vec2, size: Vec2) -> Self {
-            Self { pos, size }
+    /// First touch of a shape moving into another. `time` is the share of the
+    /// motion covered, `normal` points from the moving shape to the other.
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct Sweep {
+        pub time: f32,
+        pub normal: Vec2,
+        pub point: Vec2,
+    }
+
+    /// Where a ray enters a shape. `normal` is the surface normal, facing the
+    /// ray.
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct RayHit {
+        pub distance: f32,
+        pub point: Vec2,
+        pub normal: Vec2,
+    }
+
+    /// Shape used by the narrow phase. Boxes are described by their center
+    /// and half extents, regular polygons by the circle through their
+    /// corners. `rotation` is in radians.
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum CollisionShape {
+        Circle {
+            center: Vec2,
+            radius: f32,
+        },
+        Aabb {
+            center: Vec2,
+            half_size: Vec2,
+        },
+        Obb {
+            center: Vec2,
+            half_size: Vec2,
+            rotation: f32,
+        },
+        Polygon {
+            center: Vec2,
+            radius: f32,
+            sides: u32,
+            rotation: f32,
+        },
+    }
+
+    /// Corners of a regular polygon, the first one straight above the center
+    /// before rotating.
+    pub fn regular_polygon(center: Vec2, radius: f32, sides: u32, rotation: f32) -> Vec<Vec2> {
+        let step = std::f32::consts::TAU / sides as f32;
+        (0..sides)
+            .map(|i| {
+                let angle = rotation + step * i as f32 - std::f32::consts::FRAC_PI_2;
+                center + Vec2::from_angle(angle) * radius
+            })
+            .collect()
+    }
+
+    impl CollisionShape {
+        pub fn center(&self) -> Vec2 {
+            match *self {
+                CollisionShape::Circle { center, .. }
+                | CollisionShape::Aabb { center, .. }
+                | CollisionShape::Obb { center, .. }
+                | CollisionShape::Polygon { center, .. } => center,
+            }
+        }
+
+        pub fn translated(self, offset: Vec2) -> Self {
+            match self {
+                CollisionShape::Circle { center, radius } => CollisionShape::Circle {
+                    center: center + offset,
+                    radius,
+                },
+                CollisionShape::Aabb { center, half_size } => CollisionShape::Aabb {
+                    center: center + offset,
+                    half_size,
+                },
+                CollisionShape::Obb {
+                    center,
+                    half_size,
+                    rotation,
+                } => CollisionShape::Obb {
+                    center: center + offset,
+                    half_size,
+                    rotation,
+                },
+                CollisionShape::Polygon {
+                    center,
+                    radius,
+                    sides,
+                    rotation,
+                } => CollisionShape::Polygon {
+                    center: center + offset,
+                    radius,
+                    sides,
+                    rotation,
+                },
+            }
+        }
+
+        /// Whether the shapes overlap, touching counts.
+        pub fn intersects(&self, other: &CollisionShape) -> bool {
+            self.contact(other).is_some()
+        }
+
+        pub fn contact(&self, other: &CollisionShape) -> Option<Contact> {
+            use CollisionShape::*;
+            match (*self, *other) {
+                (
+                    Circle { center, radius },
+                    Circle {
+                        center: b,
+                        radius: rb,
+                    },
+                ) => circle_circle(center, radius, b, rb),
+                (Circle { center, radius }, Polygon { .. }) => {
+                    circle_polygon(center, radius, &other.vertices())
+                }
+                (Circle { center, radius }, _) => {
+                    let (box_center, half_size, rotation) = other.as_box();
+                    circle_box(center, radius, box_center, half_size, rotation)
+                }
+                (_, Circle { .. }) => other.contact(self).map(Contact::flipped),
+                _ => polygon_polygon(&self.vertices(), &other.vertices()),
+            }
+        }
+
+        /// Where moving by `motion` first brings the shape into `other`, which
+        /// stands still. Shapes already touching hit at time zero. Circles are
+        /// swept exactly, other shapes as the circle around them.
+        pub fn sweep(&self, motion: Vec2, other: &CollisionShape) -> Option<Sweep> {
+            let (center, radius) = match *self {
+                CollisionShape::Circle { center, radius }
+                | CollisionShape::Polygon { center, radius, .. } => (center, radius),
+                _ => {
+                    let (center, half_size, _) = self.as_box();
+                    (center, half_size.magnitude())
+                }
+            };
+            let moving = CollisionShape::Circle { center, radius };
+            if let Some(contact) = moving.contact(other) {
+                return Some(Sweep {
+                    time: 0f32,
+                    normal: contact.normal,
+                    point: contact.point,
+                });
+            }
+            match *other {
+                CollisionShape::Circle {
+                    center: target,
+                    radius: target_radius,
+                } => sweep_circle_circle(center, radius, motion, target, target_radius),
+                CollisionShape::Polygon { .. } => {
+                    sweep_circle_polygon(center, radius, motion, &other.vertices())
+                }
+                _ => {
+                    let (box_center, half_size, rotation) = other.as_box();
+                    sweep_circle_box(center, radius, motion, box_center, half_size, rotation)
+                }
+            }
+        }
+
+        /// First point within `max_distance` along `direction` where the ray
+        /// enters the shape. Rays starting inside hit at distance zero.
+        pub fn raycast(&self, origin: Vec2, direction: Vec2, max_distance: f32) -> Option<RayHit> {
+            let direction = direction.normalized();
+            if direction == Vec2::zero() {
+                return None;
+            }
+            // A ray is a point swept along it.
+            let point = CollisionShape::Circle {
+                center: origin,
+                radius: 0f32,
+            };
+            let sweep = point.sweep(direction * max_distance, self)?;
+            let distance = sweep.time * max_distance;
+            Some(RayHit {
+                distance,
+                point: origin + direction * distance,
+                normal: -sweep.normal,
+            })
         }
 
-        fn half_pos(&self) -> Vec2 {
-            self.pos + self.size / 2f32
+        /// Where the segment from `start` to `end` first enters the shape.
+        pub fn segment(&self, start: Vec2, end: Vec2) -> Option<RayHit> {
+            self.raycast(start, end - start, start.distance(end))
+        }
+
+        /// Center, half size and rotation of a box shape. Circles and
+        /// polygons give the square around them.
+        fn as_box(&self) -> (Vec2, Vec2, f32) {
+            match *self {
+                CollisionShape::Circle { center, radius }
+                | CollisionShape::Polygon { center, radius, .. } => {
+                    (center, Vec2::new(radius, radius), 0f32)
+                }
+                CollisionShape::Aabb { center, half_size } => (center, half_size, 0f32),
+                CollisionShape::Obb {
+                    center,
+                    half_size,
+                    rotation,
+                } => (center, half_size, rotation),
+            }
         }
 
-        pub fn overlap_size(&self, b: impl Into<BoxCollision>) -> Option<Vec2> {
-            let b_pos = b.into();
+        /// Corners in order around the shape, none for circles.
+        fn vertices(&self) -> Vec<Vec2> {
+            match *self {
+                CollisionShape::Circle { .. } => vec![],
+                CollisionShape::Polygon {
+                    center,
+                    radius,
+                    sides,
+                    rotation,
+                } => regular_polygon(center, radius, sides, rotation),
+                _ => box_corners(self.as_box()).to_vec(),
+            }
+        }
+    }
 
-            let a_half_pos = self.half_pos();
-            let b_half_pos = b_pos.half_pos();
-            let a_size = self.size / 2f32;
-            let b_size = b_pos.size / 2f32;
+    fn circle_circle(a: Vec2, ra: f32, b: Vec2, rb: f32) -> Option<Contact> {
+        let diff = b - a;
+        let distance = diff.magnitude();
+        if distance > ra + rb {
+            return None;
+        }
+        let normal = if distance > 0f32 {
+            diff / distance
+        } else {
+            Vec2::new(1f32, 0f32)
+        };
+        let depth = ra + rb - distance;
+        Some(Contact {
+            normal,
+            depth,
+            point: a + normal * (ra - depth / 2f32),
+        })
+    }
 
-            let diff = (b_half_pos - a_half_pos).abs();
-            let ox = a_size.x + b_size.x - diff.x;
-            let oy = a_size.y + b_size.y - diff.y;
+    /// Works in the box's frame, where the closest point of the box to the
+    /// circle is a clamp.
+    fn circle_box(
+        center: Vec2,
+        radius: f32,
+        box_center: Vec2,
+        half: Vec2,
+        rotation: f32,
+    ) -> Option<Contact> {
+        let local = (center - box_center).rotated(-rotation);
+        let closest = Vec2::new(
+            local.x.clamp(-half.x, half.x),
+            local.y.clamp(-half.y, half.y),
+        );
 
-            if ox > 0f32 && oy > 0f32 {
-                Some(Vec2::new(ox, oy))
+        // Normals point from the box to the circle until flipped below.
+        let (normal, depth, point) = if closest != local {
+            let delta = local - closest;
+            let distance = delta.magnitude();
+            if distance > radius {
+                return None;
+            }
+            (delta / distance, radius - distance, closest)
+        } else {
+            // The center is inside, leave through the nearest face.
+            let dx = half.x - local.x.abs();
+            let dy = half.y - local.y.abs();
+            if dx < dy {
+                let sign = if local.x < 0f32 { -1f32 } else { 1f32 };
+                let point = Vec2::new(sign * half.x, local.y);
+                (Vec2::new(sign, 0f32), radius + dx, point)
             } else {
-                None
+                let sign = if local.y < 0f32 { -1f32 } else { 1f32 };
+                let point = Vec2::new(local.x, sign * half.y);
+                (Vec2::new(0f32, sign), radius + dy, point)
+            }
+        };
+
+        Some(Contact {
+            normal: -normal.rotated(rotation),
+            depth,
+            point: point.rotated(rotation) + box_center,
+        })
+    }
+
+    /// Ray against the target grown by the moving radius, for shapes that
+    /// don't touch yet.
+    fn sweep_circle_circle(
+        center: Vec2,
+        radius: f32,
+        motion: Vec2,
+        target: Vec2,
+        target_radius: f32,
+    ) -> Option<Sweep> {
+        let offset = center - target;
+        let reach = radius + target_radius;
+        let a = motion.magnitude_sq();
+        let b = offset.dot(motion);
+        let c = offset.magnitude_sq() - reach * reach;
+        let discriminant = b * b - a * c;
+        if a == 0f32 || discriminant < 0f32 {
+            return None;
+        }
+        let time = (-b - discriminant.sqrt()) / a;
+        if !(0f32..=1f32).contains(&time) {
+            return None;
+        }
+        let hit = center + motion * time;
+        let normal = (target - hit).normalized();
+        Some(Sweep {
+            time,
+            normal,
+            point: hit + normal * radius,
+        })
+    }
+
+    /// Works in the box's frame. The center travels against the box grown by
+    /// the radius, whose corners are rounded, so hits in a corner region are
+    /// redone against the corner.
+    fn sweep_circle_box(
+        center: Vec2,
+        radius: f32,
+        motion: Vec2,
+        box_center: Vec2,
+        half: Vec2,
+        rotation: f32,
+    ) -> Option<Sweep> {
+        let start = (center - box_center).rotated(-rotation);
+        let local_motion = motion.rotated(-rotation);
+        let slab = |start: f32, motion: f32, half: f32| {
+            if motion == 0f32 {
+                (start.abs() <= half).then_some((f32::NEG_INFINITY, f32::INFINITY))
+            } else {
+                let (t0, t1) = ((-half - start) / motion, (half - start) / motion);
+                Some((t0.min(t1), t0.max(t1)))
+            }
+        };
+        let (x_enter, x_exit) = slab(start.x, local_motion.x, half.x + radius)?;
+        let (y_enter, y_exit) = slab(start.y, local_motion.y, half.y + radius)?;
+        let (enter, exit) = (x_enter.max(y_enter), x_exit.min(y_exit));
+        if enter > exit || enter > 1f32 || exit < 0f32 {
+            return None;
+        }
+
+        let time = enter.max(0f32);
+        let hit = start + local_motion * time;
+        let sweep = if hit.x.abs() > half.x && hit.y.abs() > half.y {
+            let corner = Vec2::new(half.x.copysign(hit.x), half.y.copysign(hit.y));
+            sweep_circle_circle(start, radius, local_motion, corner, 0f32)?
+        } else {
+            let normal = if x_enter > y_enter {
+                Vec2::new(local_motion.x.signum(), 0f32)
+            } else {
+                Vec2::new(0f32, local_motion.y.signum())
+            };
+            Sweep {
+                time,
+                normal,
+                point: hit + normal * radius,
+            }
+        };
+
+        Some(Sweep {
+            normal: sweep.normal.rotated(rotation),
+            point: sweep.point.rotated(rotation) + box_center,
+            ..sweep
+        })
+    }
+
+    fn box_corners((center, half, rotation): (Vec2, Vec2, f32)) -> [Vec2; 4] {
+        [(-1f32, -1f32), (1f32, -1f32), (1f32, 1f32), (-1f32, 1f32)]
+            .map(|(x, y)| center + Vec2::new(x * half.x, y * half.y).rotated(rotation))
+    }
+
+    fn centroid(vertices: &[Vec2]) -> Vec2 {
+        vertices.iter().fold(Vec2::zero(), |sum, &v| sum + v) / vertices.len() as f32
+    }
+
+    /// Unit normal of every edge, pointing out of the polygon.
+    fn edge_normals(vertices: &[Vec2]) -> Vec<Vec2> {
+        let center = centroid(vertices);
+        edges(vertices)
+            .map(|(a, b)| {
+                let normal = (b - a).perpendicular().normalized();
+                if normal.dot(a - center) < 0f32 {
+                    -normal
+                } else {
+                    normal
+                }
+            })
+            .collect()
+    }
+
+    fn edges(vertices: &[Vec2]) -> impl Iterator<Item = (Vec2, Vec2)> + '_ {
+        vertices
+            .iter()
+            .zip(vertices.iter().cycle().skip(1))
+            .map(|(&a, &b)| (a, b))
+    }
+
+    fn project(vertices: &[Vec2], axis: Vec2) -> (f32, f32) {
+        vertices
+            .iter()
+            .map(|v| v.dot(axis))
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), d| {
+                (min.min(d), max.max(d))
+            })
+    }
+
+    fn polygon_contains(vertices: &[Vec2], point: Vec2) -> bool {
+        const SLOP: f32 = 1e-3;
+        edges(vertices)
+            .zip(edge_normals(vertices))
+            .all(|((a, _), normal)| (point - a).dot(normal) <= SLOP)
+    }
+
+    /// Separating axis test on the edge normals of both polygons. The axis
+    /// with the smallest overlap gives the normal, corners lying inside the
+    /// other polygon give the contact point.
+    fn polygon_polygon(a: &[Vec2], b: &[Vec2]) -> Option<Contact> {
+        let diff = centroid(b) - centroid(a);
+        let mut best: Option<(Vec2, f32)> = None;
+        for axis in edge_normals(a).into_iter().chain(edge_normals(b)) {
+            let (a_min, a_max) = project(a, axis);
+            let (b_min, b_max) = project(b, axis);
+            let depth = a_max.min(b_max) - a_min.max(b_min);
+            if depth < 0f32 {
+                return None;
+            }
+            if best.is_none_or(|(_, best_depth)| depth < best_depth) {
+                let normal = if diff.dot(axis) < 0f32 { -axis } else { axis };
+                best = Some((normal, depth));
+            }
+        }
+        let (normal, depth) = best?;
+
+        let inside: Vec<Vec2> = b
+            .iter()
+            .filter(|&&corner| polygon_contains(a, corner))
+            .chain(a.iter().filter(|&&corner| polygon_contains(b, corner)))
+            .copied()
+            .collect();
+        let point = if inside.is_empty() {
+            (centroid(a) + centroid(b)) / 2f32
+        } else {
+            centroid(&inside)
+        };
+
+        Some(Contact {
+            normal,
+            depth,
+            point,
+        })
+    }
+
+    /// Separating axis test on the polygon's edge normals and the axis to its
+    /// nearest corner.
+    fn circle_polygon(center: Vec2, radius: f32, vertices: &[Vec2]) -> Option<Contact> {
+        let nearest = vertices
+            .iter()
+            .copied()
+            .min_by(|a, b| a.distance(center).total_cmp(&b.distance(center)))?;
+        let diff = centroid(vertices) - center;
+        let mut best: Option<(Vec2, f32)> = None;
+        let corner_axis = (nearest - center).normalized();
+        for axis in edge_normals(vertices).into_iter().chain([corner_axis]) {
+            if axis == Vec2::zero() {
+                continue;
+            }
+            let (min, max) = project(vertices, axis);
+            let c = center.dot(axis);
+            let depth = (c + radius).min(max) - (c - radius).max(min);
+            if depth < 0f32 {
+                return None;
             }
+            if best.is_none_or(|(_, best_depth)| depth < best_depth) {
+                let normal = if diff.dot(axis) < 0f32 { -axis } else { axis };
+                best = Some((normal, depth));
+            }
+        }
+        let (normal, depth) = best?;
+        Some(Contact {
+            normal,
+            depth,
+            point: center + normal * (radius - depth / 2f32),
+        })
+    }
+
+    /// The center travels against the polygon grown by the radius: each edge
+    /// pushed out along its normal, and a circle around each corner.
+    fn sweep_circle_polygon(
+        center: Vec2,
+        radius: f32,
+        motion: Vec2,
+        vertices: &[Vec2],
+    ) -> Option<Sweep> {
+        let edge_hits =
+            edges(vertices)
+                .zip(edge_normals(vertices))
+                .filter_map(|((a, b), normal)| {
+                    let approach = motion.dot(normal);
+                    if approach >= 0f32 {
+                        return None;
+                    }
+                    let time = (a + normal * radius - center).dot(normal) / approach;
+                    if !(0f32..=1f32).contains(&time) {
+                        return None;
+                    }
+                    let hit = center + motion * time;
+                    let along = (hit - a).dot((b - a).normalized());
+                    (0f32..=a.distance(b)).contains(&along).then(|| Sweep {
+                        time,
+                        normal: -normal,
+                        point: hit - normal * radius,
+                    })
+                });
+        let corner_hits = vertices
+            .iter()
+            .filter_map(|&corner| sweep_circle_circle(center, radius, motion, corner, 0f32));
+        edge_hits
+            .chain(corner_hits)
+            .min_by(|a, b| a.time.total_cmp(&b.time))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use std::f32::consts::FRAC_PI_4;
+
+        use super::{regular_polygon, CollisionShape, Contact, RayHit};
+
+        const BULLET_RADIUS: f32 = 12f32;
+        // A frame at 10 FPS.
+        const DT: f32 = 0.1;
+        use crate::math::Vec2;
+
+        fn assert_close(a: Vec2, b: Vec2) {
+            assert!(a.distance(b) < 1e-3, "{:?} != {:?}", a, b);
+        }
+
+        fn circle(x: f32, y: f32, radius: f32) -> CollisionShape {
+            CollisionShape::Circle {
+                center: Vec2::new(x, y),
+                radius,
+            }
+        }
+
+        fn aabb(x: f32, y: f32, half: f32) -> CollisionShape {
+            CollisionShape::Aabb {
+                center: Vec2::new(x, y),
+                half_size: Vec2::new(half, half),
+            }
+        }
+
+        fn obb(x: f32, y: f32, half: f32, rotation: f32) -> CollisionShape {
+            CollisionShape::Obb {
+                center: Vec2::new(x, y),
+                half_size: Vec2::new(half, half),
+                rotation,
+            }
+        }
+
+        fn polygon(x: f32, y: f32, radius: f32, sides: u32) -> CollisionShape {
+            CollisionShape::Polygon {
+                center: Vec2::new(x, y),
+                radius,
+                sides,
+                rotation: 0f32,
+            }
+        }
+
+        #[test]
+        fn test_circle_circle() {
+            assert!(circle(0f32, 0f32, 10f32).intersects(&circle(15f32, 0f32, 5f32)));
+            assert!(!circle(0f32, 0f32, 10f32).intersects(&circle(15f32, 0f32, 4f32)));
+        }
+
+        #[test]
+        fn test_circles_miss_at_box_corners() {
+            // Their bounding boxes overlap, the circles do not.
+            let a = circle(0f32, 0f32, 10f32);
+            let b = circle(18f32, 18f32, 10f32);
+            assert!(aabb(0f32, 0f32, 10f32).intersects(&aabb(18f32, 18f32, 10f32)));
+            assert!(!a.intersects(&b));
+        }
+
+        #[test]
+        fn test_circle_aabb() {
+            let square = aabb(0f32, 0f32, 10f32);
+            assert!(circle(15f32, 0f32, 5f32).intersects(&square));
+            assert!(square.intersects(&circle(15f32, 0f32, 5f32)));
+            assert!(!circle(14f32, 14f32, 5f32).intersects(&square));
+            assert!(circle(13f32, 13f32, 5f32).intersects(&square));
+            assert!(circle(0f32, 0f32, 1f32).intersects(&square));
+        }
+
+        #[test]
+        fn test_aabb_aabb() {
+            assert!(aabb(0f32, 0f32, 10f32).intersects(&aabb(20f32, 0f32, 10f32)));
+            assert!(!aabb(0f32, 0f32, 10f32).intersects(&aabb(21f32, 5f32, 10f32)));
+        }
+
+        #[test]
+        fn test_circle_obb() {
+            // A diamond reaches further along the axes than the square.
+            let diamond = obb(0f32, 0f32, 10f32, FRAC_PI_4);
+            assert!(circle(16f32, 0f32, 2f32).intersects(&diamond));
+            assert!(!circle(16f32, 0f32, 2f32).intersects(&aabb(0f32, 0f32, 10f32)));
+            assert!(!circle(12f32, 12f32, 2f32).intersects(&diamond));
+        }
+
+        #[test]
+        fn test_obb_obb() {
+            let diamond = obb(0f32, 0f32, 10f32, FRAC_PI_4);
+            assert!(diamond.intersects(&aabb(22f32, 0f32, 10f32)));
+            assert!(!diamond.intersects(&aabb(25f32, 0f32, 10f32)));
+            // Corners of the square face the diamond's flat side.
+            assert!(!diamond.intersects(&aabb(18f32, 18f32, 10f32)));
+            assert!(obb(0f32, 0f32, 10f32, 0f32).intersects(&aabb(18f32, 18f32, 10f32)));
+        }
+
+        #[test]
+        fn test_circle_circle_contact() {
+            let contact = circle(0f32, 0f32, 10f32)
+                .contact(&circle(15f32, 0f32, 10f32))
+                .unwrap();
+            assert_close(contact.normal, Vec2::new(1f32, 0f32));
+            assert!((contact.depth - 5f32).abs() < 1e-5);
+            assert_close(contact.point, Vec2::new(7.5, 0f32));
+        }
+
+        #[test]
+        fn test_circle_box_contact() {
+            let square = aabb(0f32, 0f32, 10f32);
+            let contact = circle(0f32, 14f32, 5f32).contact(&square).unwrap();
+            assert_close(contact.normal, Vec2::new(0f32, -1f32));
+            assert!((contact.depth - 1f32).abs() < 1e-5);
+            assert_close(contact.point, Vec2::new(0f32, 10f32));
+
+            let flipped = square.contact(&circle(0f32, 14f32, 5f32)).unwrap();
+            assert_eq!(flipped, contact.flipped());
+        }
+
+        #[test]
+        fn test_circle_inside_box_leaves_through_nearest_face() {
+            let contact = circle(-8f32, 2f32, 1f32)
+                .contact(&aabb(0f32, 0f32, 10f32))
+                .unwrap();
+            assert_close(contact.normal, Vec2::new(1f32, 0f32));
+            assert!((contact.depth - 3f32).abs() < 1e-5);
+        }
+
+        #[test]
+        fn test_box_box_contact() {
+            let contact = aabb(0f32, 0f32, 10f32)
+                .contact(&aabb(18f32, 5f32, 10f32))
+                .unwrap();
+            assert_close(contact.normal, Vec2::new(1f32, 0f32));
+            assert!((contact.depth - 2f32).abs() < 1e-5);
+            assert_close(contact.point, Vec2::new(9f32, 2.5));
+
+            let Contact { normal, depth, .. } = aabb(0f32, 0f32, 10f32)
+                .contact(&obb(0f32, -22f32, 10f32, FRAC_PI_4))
+                .unwrap();
+            assert_close(normal, Vec2::new(0f32, -1f32));
+            assert!((depth - (10f32 + 10f32 * 2f32.sqrt() - 22f32)).abs() < 1e-4);
+        }
+
+        #[test]
+        fn test_regular_polygon() {
+            let corners = regular_polygon(Vec2::new(5f32, 5f32), 10f32, 4, 0f32);
+            assert_eq!(corners.len(), 4);
+            assert_close(corners[0], Vec2::new(5f32, -5f32));
+            assert_close(corners[1], Vec2::new(15f32, 5f32));
+        }
+
+        #[test]
+        fn test_square_polygon_matches_aabb() {
+            let square = CollisionShape::Polygon {
+                center: Vec2::zero(),
+                radius: 10f32 * 2f32.sqrt(),
+                sides: 4,
+                rotation: FRAC_PI_4,
+            };
+            let other = aabb(18f32, 5f32, 10f32);
+            assert_eq!(
+                square.contact(&other).map(|c| (c.depth * 1e3).round()),
+                aabb(0f32, 0f32, 10f32)
+                    .contact(&other)
+                    .map(|c| (c.depth * 1e3).round())
+            );
+            assert!(!square.intersects(&aabb(21f32, 5f32, 10f32)));
+        }
+
+        #[test]
+        fn test_circle_polygon() {
+            // Tip up, flat bottom edge at y = 5.
+            let triangle = polygon(0f32, 0f32, 10f32, 3);
+            assert!(circle(0f32, -14f32, 5f32).intersects(&triangle));
+            assert!(!circle(0f32, 12f32, 5f32).intersects(&triangle));
+            // Clear of the tip even though it is inside the bounding circle.
+            assert!(!circle(-8f32, -8f32, 2f32).intersects(&triangle));
+
+            let contact = circle(0f32, 9f32, 5f32).contact(&triangle).unwrap();
+            assert_close(contact.normal, Vec2::new(0f32, -1f32));
+            assert!((contact.depth - 1f32).abs() < 1e-4);
+            let flipped = triangle.contact(&circle(0f32, 9f32, 5f32)).unwrap();
+            assert_eq!(flipped, contact.flipped());
+        }
+
+        #[test]
+        fn test_polygon_box() {
+            let triangle = polygon(0f32, 0f32, 10f32, 3);
+            assert!(!triangle.intersects(&aabb(0f32, -22f32, 10f32)));
+            let contact = triangle.contact(&aabb(0f32, -19f32, 10f32)).unwrap();
+            assert_close(contact.normal, Vec2::new(0f32, -1f32));
+            assert!((contact.depth - 1f32).abs() < 1e-4);
+            assert_close(contact.point, Vec2::new(0f32, -10f32));
+        }
+
+        #[test]
+        fn test_polygon_polygon() {
+            let a = polygon(0f32, 0f32, 10f32, 6);
+            // Flat sides of the hexagons face each other along x.
+            let half_width = 10f32 * (3f32.sqrt() / 2f32);
+            assert!(a.intersects(&polygon(2f32 * half_width - 1f32, 0f32, 10f32, 6)));
+            assert!(!a.intersects(&polygon(2f32 * half_width + 1f32, 0f32, 10f32, 6)));
+            let contact = a
+                .contact(&polygon(2f32 * half_width - 1f32, 0f32, 10f32, 6))
+                .unwrap();
+            assert_close(contact.normal, Vec2::new(1f32, 0f32));
+            assert!((contact.depth - 1f32).abs() < 1e-4);
+        }
+
+        #[test]
+        fn test_sweep_circle_circle() {
+            let bullet = circle(0f32, 0f32, 5f32);
+            let sweep = bullet
+                .sweep(Vec2::new(100f32, 0f32), &circle(50f32, 0f32, 5f32))
+                .unwrap();
+            assert!((sweep.time - 0.4).abs() < 1e-5);
+            assert_close(sweep.normal, Vec2::new(1f32, 0f32));
+            assert_close(sweep.point, Vec2::new(45f32, 0f32));
+
+            assert!(bullet
+                .sweep(Vec2::new(100f32, 0f32), &circle(50f32, 11f32, 5f32))
+                .is_none());
+            assert!(bullet
+                .sweep(Vec2::new(-100f32, 0f32), &circle(50f32, 0f32, 5f32))
+                .is_none());
+            assert!(bullet
+                .sweep(Vec2::new(30f32, 0f32), &circle(50f32, 0f32, 5f32))
+                .is_none());
+        }
+
+        #[test]
+        fn test_sweep_circle_box_face_and_corner() {
+            let square = aabb(0f32, 0f32, 10f32);
+            let sweep = circle(-30f32, 5f32, 5f32)
+                .sweep(Vec2::new(40f32, 0f32), &square)
+                .unwrap();
+            assert!((sweep.time - 0.375).abs() < 1e-5);
+            assert_close(sweep.normal, Vec2::new(1f32, 0f32));
+            assert_close(sweep.point, Vec2::new(-10f32, 5f32));
+
+            // Cuts through the grown box but misses its rounded corner.
+            assert!(circle(-25f32, 4f32, 5f32)
+                .sweep(Vec2::new(20f32, 20f32), &square)
+                .is_none());
+            let sweep = circle(-30f32, 13f32, 5f32)
+                .sweep(Vec2::new(40f32, 0f32), &square)
+                .unwrap();
+            assert!((sweep.time - 0.4).abs() < 1e-5);
+            assert_close(sweep.point, Vec2::new(-10f32, 10f32));
+            assert_close(sweep.normal, Vec2::new(0.8, -0.6));
+        }
+
+        #[test]
+        fn test_sweep_circle_obb() {
+            let diamond = obb(0f32, 0f32, 10f32, FRAC_PI_4);
+            let sweep = circle(-40f32, 0f32, 2f32)
+                .sweep(Vec2::new(40f32, 0f32), &diamond)
+                .unwrap();
+            let tip = 10f32 * 2f32.sqrt();
+            assert_close(sweep.point, Vec2::new(-tip, 0f32));
+        }
+
+        #[test]
+        fn test_sweep_circle_polygon() {
+            let triangle = polygon(0f32, 0f32, 10f32, 3);
+            let sweep = circle(0f32, 40f32, 5f32)
+                .sweep(Vec2::new(0f32, -40f32), &triangle)
+                .unwrap();
+            assert!((sweep.time - 0.75).abs() < 1e-5);
+            assert_close(sweep.normal, Vec2::new(0f32, -1f32));
+            assert_close(sweep.point, Vec2::new(0f32, 5f32));
+
+            // Passes beside the tip.
+            assert!(circle(-20f32, -13f32, 2f32)
+                .sweep(Vec2::new(40f32, 0f32), &triangle)
+                .is_none());
+            let sweep = circle(0f32, -30f32, 2f32)
+                .sweep(Vec2::new(0f32, 40f32), &triangle)
+                .unwrap();
+            assert!((sweep.time - 0.45).abs() < 1e-5);
+            assert_close(sweep.point, Vec2::new(0f32, -10f32));
         }
 
-        pub fn collide_aabb(&self, b: &BoxCollision) -> bool {
-            self.horizontal_overlap(b) && self.vertical_overlap(b)
+        #[test]
+        fn test_touching_shapes_hit_at_once() {
+            let sweep = circle(0f32, 0f32, 5f32)
+                .sweep(Vec2::new(10f32, 0f32), &aabb(8f32, 0f32, 5f32))
+                .unwrap();
+            assert_eq!(sweep.time, 0f32);
         }
 
-        pub fn horizontal_overlap(&self, b: &BoxCollision) -> bool {
-            self.pos.y <= (b.pos.y + b.size.y) && b.pos.y <= (self.pos.y + self.size.y)
+        #[test]
+        fn test_fast_bullet_cannot_skip_target_at_10_fps() {
+            // Fast enough to land on either side of the enemy on consecutive
+            // frames.
+            let speed = 1200f32;
+            let enemy = aabb(0f32, 0f32, 32f32);
+            let start = circle(-50f32, 0f32, BULLET_RADIUS);
+            let motion = Vec2::new(speed * DT, 0f32);
+            let end = circle(-50f32 + motion.x, 0f32, BULLET_RADIUS);
+
+            assert!(!start.intersects(&enemy) && !end.intersects(&enemy));
+            let sweep = start.sweep(motion, &enemy).unwrap();
+            assert!((sweep.time - (50f32 - 32f32 - BULLET_RADIUS) / motion.x).abs() < 1e-5);
+        }
+
+        #[test]
+        fn test_raycast_circle() {
+            let target = circle(50f32, 0f32, 10f32);
+            let hit = target
+                .raycast(Vec2::zero(), Vec2::new(2f32, 0f32), 100f32)
+                .unwrap();
+            assert!((hit.distance - 40f32).abs() < 1e-4);
+            assert_close(hit.point, Vec2::new(40f32, 0f32));
+            assert_close(hit.normal, Vec2::new(-1f32, 0f32));
+
+            assert!(target
+                .raycast(Vec2::zero(), Vec2::new(1f32, 0f32), 30f32)
+                .is_none());
+            assert!(target
+                .raycast(Vec2::zero(), Vec2::new(-1f32, 0f32), 100f32)
+                .is_none());
+            assert!(target.raycast(Vec2::zero(), Vec2::zero(), 100f32).is_none());
+        }
+
+        #[test]
+        fn test_raycast_boxes() {
+            let hit = aabb(0f32, 0f32, 10f32)
+                .raycast(Vec2::new(5f32, 30f32), Vec2::new(0f32, -1f32), 100f32)
+                .unwrap();
+            assert!((hit.distance - 20f32).abs() < 1e-4);
+            assert_close(hit.point, Vec2::new(5f32, 10f32));
+            assert_close(hit.normal, Vec2::new(0f32, 1f32));
+
+            let diamond = obb(0f32, 0f32, 10f32, FRAC_PI_4);
+            let hit = diamond
+                .raycast(Vec2::new(-30f32, 0f32), Vec2::new(1f32, 0f32), 100f32)
+                .unwrap();
+            assert!((hit.distance - (30f32 - 10f32 * 2f32.sqrt())).abs() < 1e-4);
+            // Passes above the diamond.
+            assert!(diamond
+                .raycast(Vec2::new(-30f32, 15f32), Vec2::new(1f32, 0f32), 100f32)
+                .is_none());
+        }
+
+        #[test]
+        fn test_raycast_polygon() {
+            let hexagon = polygon(0f32, 0f32, 10f32, 6);
+            let hit = hexagon
+                .raycast(Vec2::new(-30f32, 0f32), Vec2::new(1f32, 0f32), 100f32)
+                .unwrap();
+            let half_width = 10f32 * (3f32.sqrt() / 2f32);
+            assert!((hit.distance - (30f32 - half_width)).abs() < 1e-4);
+            assert_close(hit.normal, Vec2::new(-1f32, 0f32));
+        }
+
+        #[test]
+        fn test_ray_starting_inside() {
+            let RayHit {
+                distance, point, ..
+            } = aabb(0f32, 0f32, 10f32)
+                .raycast(Vec2::new(2f32, 3f32), Vec2::new(1f32, 0f32), 100f32)
+                .unwrap();
+            assert_eq!(distance, 0f32);
+            assert_eq!(point, Vec2::new(2f32, 3f32));
         }
 
-        pub fn vertical_overlap(&self, b: &BoxCollision) -> bool {
-            self.pos.x <= (b.pos.x + b.size.x) && b.pos.x <= (self.pos.x + self.size.x)
+        #[test]
+        fn test_segment() {
+            let square = aabb(0f32, 0f32, 10f32);
+            let hit = square
+                .segment(Vec2::new(-30f32, 0f32), Vec2::new(30f32, 0f32))
+                .unwrap();
+            assert_close(hit.point, Vec2::new(-10f32, 0f32));
+            assert!(square
+                .segment(Vec2::new(-30f32, 0f32), Vec2::new(-15f32, 0f32))
+                .is_none());
+            assert!(circle(0f32, 0f32, 5f32)
+                .segment(Vec2::new(-30f32, 5f32), Vec2::new(30f32, 5f32))
+                .is_some());
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::{FRAC_PI_2, PI};
+
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::{Affine2, Vec2};
+
+    const EPSILON: f32 = 1e-3;
+    const CASES: usize = 1000;
+
+    fn assert_close(a: Vec2, b: Vec2) {
+        assert!(a.approx_eq(b, EPSILON), "{:?} != {:?}", a, b);
+    }
+
+    /// Runs `property` on random vectors, seeded so failures reproduce.
+    fn for_random_vectors(property: impl Fn(Vec2, Vec2, f32)) {
+        let mut rng = StdRng::seed_from_u64(4300);
+        let vector = |rng: &mut StdRng| {
+            Vec2::new(
+                rng.gen_range(-100f32..100f32),
+                rng.gen_range(-100f32..100f32),
+            )
+        };
+        for _ in 0..CASES {
+            let (a, b) = (vector(&mut rng), vector(&mut rng));
+            property(a, b, rng.gen_range(-PI..PI));
+        }
+    }
+
+    #[test]
+    fn test_operators() {
+        let mut v = Vec2::new(1f32, 2f32);
+        v += Vec2::new(2f32, 2f32);
+        v -= Vec2::new(1f32, 0f32);
+        v *= 2f32;
+        v /= 4f32;
+        assert_eq!(v, Vec2::new(1f32, 2f32));
+        assert_eq!(-v, Vec2::new(-1f32, -2f32));
+        assert_eq!(v * Vec2::new(3f32, -1f32), Vec2::new(3f32, -2f32));
+        assert_eq!(2f32 * v, v * 2f32);
+    }
+
+    #[test]
+    fn test_angles() {
+        assert_close(Vec2::from_angle(FRAC_PI_2), Vec2::new(0f32, 1f32));
+        assert!((Vec2::new(0f32, -3f32).angle() + FRAC_PI_2).abs() < EPSILON);
+        let right = Vec2::new(1f32, 0f32);
+        assert!((right.angle_to(Vec2::new(0f32, 2f32)) - FRAC_PI_2).abs() < EPSILON);
+        assert!((Vec2::new(0f32, 2f32).angle_to(right) + FRAC_PI_2).abs() < EPSILON);
+    }
+
+    #[test]
+    fn test_helpers() {
+        let v = Vec2::new(3f32, 4f32);
+        assert_eq!(v.cross(Vec2::new(1f32, 0f32)), -4f32);
+        assert_eq!(v.perpendicular(), Vec2::new(-4f32, 3f32));
+        assert_eq!(Vec2::zero().lerp(v, 0.5), Vec2::new(1.5, 2f32));
+        assert_eq!(v.reflect(Vec2::new(0f32, -1f32)), Vec2::new(3f32, -4f32));
+        assert_close(v.clamp_length(2.5), Vec2::new(1.5, 2f32));
+        assert_eq!(v.clamp_length(10f32), v);
+        assert_eq!(v.project_onto(Vec2::new(2f32, 0f32)), Vec2::new(3f32, 0f32));
+        assert_eq!(v.project_onto(Vec2::zero()), Vec2::zero());
+    }
+
+    #[test]
+    fn test_rotation_properties() {
+        for_random_vectors(|a, b, angle| {
+            let (ra, rb) = (a.rotated(angle), b.rotated(angle));
+            assert!((ra.magnitude() - a.magnitude()).abs() < EPSILON);
+            assert!((ra.dot(rb) - a.dot(b)).abs() < 0.1);
+            assert!((ra.cross(rb) - a.cross(b)).abs() < 0.1);
+            assert_close(ra.rotated(-angle), a);
+            if a != Vec2::zero() {
+                assert_close(a.rotated(a.angle_to(b)).normalized(), b.normalized());
+            }
+        });
+    }
+
+    #[test]
+    fn test_reflect_and_project_properties() {
+        for_random_vectors(|a, b, angle| {
+            let normal = Vec2::from_angle(angle);
+            let reflected = a.reflect(normal);
+            assert!((reflected.magnitude() - a.magnitude()).abs() < EPSILON);
+            assert!((reflected.dot(normal) + a.dot(normal)).abs() < EPSILON);
+            assert_close(reflected.reflect(normal), a);
+
+            let projected = a.project_onto(b);
+            assert!((a - projected).dot(b).abs() < 0.1);
+            assert!(projected.cross(b).abs() < 0.1);
+        });
+    }
+
+    #[test]
+    fn test_lerp_and_clamp_properties() {
+        for_random_vectors(|a, b, angle| {
+            assert_close(a.lerp(b, 0f32), a);
+            assert_close(a.lerp(b, 1f32), b);
+            let max = angle.abs() * 10f32;
+            let clamped = a.clamp_length(max);
+            assert!(clamped.magnitude() <= max + EPSILON);
+            assert!(clamped.cross(a).abs() < 0.1);
+        });
+    }
+
+    #[test]
+    fn test_affine_composition_order() {
+        let transform = Affine2::from_scale_angle_translation(
+            Vec2::new(2f32, 3f32),
+            FRAC_PI_2,
+            Vec2::new(10f32, 0f32),
+        );
+        // Scaled to (2, 0), turned to (0, 2), then moved.
+        assert_close(
+            transform.transform_point(Vec2::new(1f32, 0f32)),
+            Vec2::new(10f32, 2f32),
+        );
+        assert_close(
+            transform.transform_vector(Vec2::new(0f32, 1f32)),
+            Vec2::new(-3f32, 0f32),
+        );
+        assert!((transform.determinant() - 6f32).abs() < EPSILON);
+        assert_eq!(Affine2::IDENTITY * transform, transform);
+    }
+
+    #[test]
+    fn test_affine_inverse() {
+        assert!(Affine2::from_scale(Vec2::new(0f32, 1f32))
+            .inverse()
+            .is_none());
+        for_random_vectors(|a, b, angle| {
+            let scale = Vec2::new(1f32 + angle.abs(), 2f32);
+            let transform = Affine2::from_scale_angle_translation(scale, angle, b);
+            let inverse = transform.inverse().unwrap();
+            assert_close(inverse.transform_point(transform.transform_point(a)), a);
+            assert_close(
+                transform.transform_point(a),
+                (transform * Affine2::from_translation(a)).translation,
+            );
+            assert_close(transform.transform_point(a), b + (a * scale).rotated(angle));
+        });
+    }
+}
@@ -0,0 +1,145 @@
+use std::collections::BTreeSet;
+
+use ecs::entity::EntityId;
+
+use crate::event::{EventReceiver, EventSender};
+use crate::math::collision::Contact;
+
+/// Two colliders that touch. `a` has the lower id and `contact.normal`
+/// points from `a` to `b`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Collision {
+    pub a: EntityId,
+    pub b: EntityId,
+    pub contact: Contact,
+}
+
+impl Collision {
+    /// `contact` is seen from `a`, the pair is reordered when needed.
+    pub fn new(a: EntityId, b: EntityId, contact: Contact) -> Self {
+        if a <= b {
+            Self { a, b, contact }
+        } else {
+            Self {
+                a: b,
+                b: a,
+                contact: contact.flipped(),
+            }
+        }
+    }
+}
+
+/// Sent on the first frame a pair touches.
+pub struct CollisionStarted(pub Collision);
+
+/// Sent on every following frame the pair keeps touching.
+pub struct CollisionOngoing(pub Collision);
+
+/// Sent once the pair stops touching or one of them is gone, lower id first.
+pub struct CollisionEnded(pub EntityId, pub EntityId);
+
+/// Turns the pairs touching each frame into started, ongoing and ended
+/// collision events.
+#[derive(Default)]
+pub struct ContactTracker {
+    touching: BTreeSet<(EntityId, EntityId)>,
+}
+
+impl ContactTracker {
+    /// Readers are expected to `peek`, so the previous frame's events are
+    /// dropped here before the new ones go out.
+    pub fn update<E>(&mut self, collisions: Vec<Collision>, events: &mut E)
+    where
+        E: EventSender<CollisionStarted>
+            + EventSender<CollisionOngoing>
+            + EventSender<CollisionEnded>
+            + EventReceiver<CollisionStarted>
+            + EventReceiver<CollisionOngoing>
+            + EventReceiver<CollisionEnded>,
+    {
+        let _: Vec<CollisionStarted> = events.read();
+        let _: Vec<CollisionOngoing> = events.read();
+        let _: Vec<CollisionEnded> = events.read();
+
+        let mut touching = BTreeSet::new();
+        for collision in collisions {
+            let pair = (collision.a, collision.b);
+            if !touching.insert(pair) {
+                continue;
+            }
+            if self.touching.contains(&pair) {
+                events.send(CollisionOngoing(collision));
+            } else {
+                events.send(CollisionStarted(collision));
+            }
+        }
+        for &(a, b) in self.touching.difference(&touching) {
+            events.send(CollisionEnded(a, b));
+        }
+        self.touching = touching;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Collision, CollisionEnded, CollisionOngoing, CollisionStarted, ContactTracker};
+    use crate::event::{EventReceiver, EventSystem};
+    use crate::math::collision::Contact;
+    use crate::math::Vec2;
+
+    fn collision(a: u64, b: u64) -> Collision {
+        Collision::new(
+            a,
+            b,
+            Contact {
+                normal: Vec2::new(1f32, 0f32),
+                depth: 1f32,
+                point: Vec2::zero(),
+            },
+        )
+    }
+
+    type Pairs = Vec<(u64, u64)>;
+
+    fn pairs(events: &EventSystem) -> (Pairs, Pairs, Pairs) {
+        let started: Vec<&CollisionStarted> = events.peek();
+        let ongoing: Vec<&CollisionOngoing> = events.peek();
+        let ended: Vec<&CollisionEnded> = events.peek();
+        (
+            started.iter().map(|e| (e.0.a, e.0.b)).collect(),
+            ongoing.iter().map(|e| (e.0.a, e.0.b)).collect(),
+            ended.iter().map(|e| (e.0, e.1)).collect(),
+        )
+    }
+
+    #[test]
+    fn test_new_orders_pair() {
+        let collision = collision(5, 2);
+        assert_eq!((collision.a, collision.b), (2, 5));
+        assert_eq!(collision.contact.normal, Vec2::new(-1f32, 0f32));
+    }
+
+    #[test]
+    fn test_started_ongoing_ended() {
+        let mut tracker = ContactTracker::default();
+        let mut events = EventSystem::default();
+
+        tracker.update(vec![collision(1, 2), collision(3, 4)], &mut events);
+        assert_eq!(pairs(&events), (vec![(1, 2), (3, 4)], vec![], vec![]));
+
+        tracker.update(vec![collision(2, 1), collision(5, 6)], &mut events);
+        assert_eq!(pairs(&events), (vec![(5, 6)], vec![(1, 2)], vec![(3, 4)]));
+
+        tracker.update(vec![], &mut events);
+        assert_eq!(pairs(&events), (vec![], vec![], vec![(1, 2), (5, 6)]));
+    }
+
+    #[test]
+    fn test_duplicate_pairs_sent_once() {
+        let mut tracker = ContactTracker::default();
+        let mut events = EventSystem::default();
+
+        tracker.update(vec![collision(1, 2), collision(2, 1)], &mut events);
+        assert_eq!(pairs(&events), (vec![(1, 2)], vec![], vec![]));
+    }
+}
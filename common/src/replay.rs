@@ -0,0 +1,236 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+use ggez::{GameError, GameResult};
+
+use crate::input::{Binding, InputState};
+use crate::math::Vec2;
+
+#[derive(Clone, Debug)]
+pub struct ReplayFrame {
+    pub delta: Duration,
+    pub input: InputState,
+}
+
+/// Seed and per-frame input of a session, plus the outcome it ended with.
+///
+/// Stored as plain text, one entry per line:
+/// ```text
+/// seed <u64>
+/// frame <delta nanos> <mouse x> <mouse y> <left x> <left y> <right x> <right y> [binding..]
+/// outcome <name> <i64>
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Replay {
+    pub seed: u64,
+    pub frames: Vec<ReplayFrame>,
+    pub outcome: BTreeMap<String, i64>,
+}
+
+impl Replay {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            ..Default::default()
+        }
+    }
+
+    pub fn push(&mut self, delta: Duration, input: InputState) {
+        self.frames.push(ReplayFrame { delta, input });
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> GameResult<Self> {
+        std::fs::read_to_string(path)?.parse()
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> GameResult<()> {
+        std::fs::write(path, self.to_string())?;
+        Ok(())
+    }
+}
+
+impl Display for Replay {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "seed {}", self.seed)?;
+        for frame in &self.frames {
+            let input = &frame.input;
+            write!(
+                f,
+                "frame {} {} {} {} {} {} {}",
+                frame.delta.as_nanos(),
+                input.mouse_position.x,
+                input.mouse_position.y,
+                input.gamepad.left_stick.x,
+                input.gamepad.left_stick.y,
+                input.gamepad.right_stick.x,
+                input.gamepad.right_stick.y,
+            )?;
+            let mut names: Vec<&str> = input.pressed().iter().filter_map(|b| b.name()).collect();
+            names.sort_unstable();
+            for name in names {
+                write!(f, " {}", name)?;
+            }
+            writeln!(f)?;
+        }
+        for (name, value) in &self.outcome {
+            writeln!(f, "outcome {} {}", name, value)?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_value<T: FromStr>(value: Option<&str>, line: usize) -> GameResult<T> {
+    value
+        .and_then(|v| v.parse().ok())
+        .ok_or_else(|| GameError::CustomError(format!("Invalid replay entry on line {}", line + 1)))
+}
+
+fn parse_frame<'a>(
+    mut values: impl Iterator<Item = &'a str>,
+    line: usize,
+) -> GameResult<ReplayFrame> {
+    let delta = Duration::from_nanos(parse_value(values.next(), line)?);
+    let mut input = InputState {
+        mouse_position: Vec2::new(
+            parse_value(values.next(), line)?,
+            parse_value(values.next(), line)?,
+        ),
+        ..Default::default()
+    };
+    input.gamepad.left_stick = Vec2::new(
+        parse_value(values.next(), line)?,
+        parse_value(values.next(), line)?,
+    );
+    input.gamepad.right_stick = Vec2::new(
+        parse_value(values.next(), line)?,
+        parse_value(values.next(), line)?,
+    );
+    for name in values {
+        input.press(name.parse::<Binding>()?);
+    }
+    Ok(ReplayFrame { delta, input })
+}
+
+impl FromStr for Replay {
+    type Err = GameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut replay = Replay::default();
+        for (line, content) in s.lines().enumerate() {
+            let mut values = content.split_whitespace();
+            match values.next() {
+                Some("seed") => replay.seed = parse_value(values.next(), line)?,
+                Some("frame") => replay.frames.push(parse_frame(values, line)?),
+                Some("outcome") => {
+                    let name = parse_value(values.next(), line)?;
+                    let value = parse_value(values.next(), line)?;
+                    replay.outcome.insert(name, value);
+                }
+                None => {}
+                Some(entry) => {
+                    return Err(GameError::CustomError(format!(
+                        "Unknown replay entry {} on line {}",
+                        entry,
+                        line + 1
+                    )))
+                }
+            }
+        }
+        Ok(replay)
+    }
+}
+
+/// Hands out the recorded frames one update at a time.
+pub struct ReplayPlayer {
+    replay: Replay,
+    cursor: usize,
+}
+
+impl ReplayPlayer {
+    pub fn new(replay: Replay) -> Self {
+        Self { replay, cursor: 0 }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.replay.seed
+    }
+
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    pub fn next_frame(&mut self) -> Option<&ReplayFrame> {
+        let frame = self.replay.frames.get(self.cursor);
+        if frame.is_some() {
+            self.cursor += 1;
+        }
+        frame
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use ggez::event::{Axis, Button, KeyCode, MouseButton};
+
+    use super::{Replay, ReplayPlayer};
+    use crate::input::InputState;
+    use crate::math::Vec2;
+
+    #[test]
+    fn test_round_trip() {
+        let mut input = InputState {
+            mouse_position: Vec2::new(10.5, -3.25),
+            ..Default::default()
+        };
+        input.keys.insert(KeyCode::W);
+        input.keys.insert(KeyCode::LShift);
+        input.mouse_buttons.insert(MouseButton::Left);
+        input.gamepad.button_down(Button::RightTrigger2);
+        input.gamepad.axis_changed(Axis::LeftStickX, 0.3);
+
+        let mut replay = Replay::new(99);
+        replay.push(Duration::from_nanos(16_666_667), input);
+        replay.push(Duration::from_millis(17), InputState::default());
+        replay.outcome.insert("score".to_string(), -400);
+
+        let parsed: Replay = replay.to_string().parse().unwrap();
+
+        assert_eq!(parsed.seed, 99);
+        assert_eq!(parsed.frames.len(), 2);
+        assert_eq!(parsed.outcome.get("score"), Some(&-400));
+
+        let frame = &parsed.frames[0];
+        assert_eq!(frame.delta, Duration::from_nanos(16_666_667));
+        assert_eq!(frame.input.mouse_position, Vec2::new(10.5, -3.25));
+        assert_eq!(frame.input.gamepad.left_stick, Vec2::new(0.3, 0f32));
+        assert!(frame.input.keys.contains(&KeyCode::W));
+        assert!(frame.input.keys.contains(&KeyCode::LShift));
+        assert!(frame.input.mouse_buttons.contains(&MouseButton::Left));
+        assert!(frame.input.gamepad.buttons.contains(&Button::RightTrigger2));
+        assert!(parsed.frames[1].input.keys.is_empty());
+    }
+
+    #[test]
+    fn test_invalid_entry() {
+        assert!("seed abc".parse::<Replay>().is_err());
+        assert!("jump 1".parse::<Replay>().is_err());
+        assert!("frame 1 2".parse::<Replay>().is_err());
+    }
+
+    #[test]
+    fn test_player() {
+        let mut replay = Replay::new(1);
+        replay.push(Duration::from_millis(1), InputState::default());
+        replay.push(Duration::from_millis(2), InputState::default());
+        let mut player = ReplayPlayer::new(replay);
+
+        assert_eq!(player.next_frame().unwrap().delta, Duration::from_millis(1));
+        assert_eq!(player.next_frame().unwrap().delta, Duration::from_millis(2));
+        assert!(player.next_frame().is_none());
+    }
+}
@@ -0,0 +1,301 @@
+use std::collections::HashMap;
+
+use ggez::graphics::Rect;
+
+use crate::math::Vec2;
+
+pub const DEFAULT_CELL_SIZE: f32 = 128f32;
+
+/// Broad phase over axis aligned bounds. Every item is stored in each cell
+/// its bounds touch, queries only look at the cells they cover. Meant to be
+/// cleared and refilled every frame.
+///
+/// Results come back in insertion order, so they don't depend on the map's
+/// iteration order.
+pub struct SpatialGrid<T> {
+    cell_size: f32,
+    cells: HashMap<(i32, i32), Vec<usize>>,
+    items: Vec<(T, Rect)>,
+}
+
+impl<T: Copy> SpatialGrid<T> {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+            items: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Empties the grid but keeps its allocations for the next frame.
+    pub fn clear(&mut self) {
+        self.cells.values_mut().for_each(Vec::clear);
+        self.items.clear();
+    }
+
+    pub fn insert(&mut self, item: T, bounds: Rect) {
+        let index = self.items.len();
+        self.items.push((item, bounds));
+        for cell in self.cells_in(bounds) {
+            self.cells.entry(cell).or_default().push(index);
+        }
+    }
+
+    fn cell(&self, point: Vec2) -> (i32, i32) {
+        (
+            (point.x / self.cell_size).floor() as i32,
+            (point.y / self.cell_size).floor() as i32,
+        )
+    }
+
+    fn cells_in(&self, area: Rect) -> impl Iterator<Item = (i32, i32)> {
+        let (x0, y0) = self.cell(Vec2::new(area.left(), area.top()));
+        let (x1, y1) = self.cell(Vec2::new(area.right(), area.bottom()));
+        (x0..=x1).flat_map(move |x| (y0..=y1).map(move |y| (x, y)))
+    }
+
+    /// Indices stored in `cells`, each once and in insertion order.
+    fn candidates(&self, cells: impl IntoIterator<Item = (i32, i32)>) -> Vec<usize> {
+        let mut indices: Vec<usize> = cells
+            .into_iter()
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+            .collect();
+        indices.sort_unstable();
+        indices.dedup();
+        indices
+    }
+
+    /// Items whose bounds overlap `area`.
+    pub fn query_rect(&self, area: Rect) -> Vec<T> {
+        self.candidates(self.cells_in(area))
+            .into_iter()
+            .filter(|&index| overlaps(self.items[index].1, area))
+            .map(|index| self.items[index].0)
+            .collect()
+    }
+
+    /// Items whose bounds come within `radius` of `center`.
+    pub fn query_radius(&self, center: Vec2, radius: f32) -> Vec<T> {
+        let area = Rect::new(
+            center.x - radius,
+            center.y - radius,
+            radius * 2f32,
+            radius * 2f32,
+        );
+        self.candidates(self.cells_in(area))
+            .into_iter()
+            .filter(|&index| {
+                let bounds = self.items[index].1;
+                let closest = Vec2::new(
+                    center.x.clamp(bounds.left(), bounds.right()),
+                    center.y.clamp(bounds.top(), bounds.bottom()),
+                );
+                closest.distance(center) <= radius
+            })
+            .map(|index| self.items[index].0)
+            .collect()
+    }
+
+    /// Every pair of items with overlapping bounds, once, the earlier
+    /// inserted item first.
+    pub fn overlapping_pairs(&self) -> Vec<(T, T)> {
+        let mut pairs = Vec::new();
+        for indices in self.cells.values() {
+            for (i, &a) in indices.iter().enumerate() {
+                for &b in &indices[i + 1..] {
+                    if overlaps(self.items[a].1, self.items[b].1) {
+                        pairs.push((a.min(b), a.max(b)));
+                    }
+                }
+            }
+        }
+        pairs.sort_unstable();
+        pairs.dedup();
+        pairs
+            .into_iter()
+            .map(|(a, b)| (self.items[a].0, self.items[b].0))
+            .collect()
+    }
+
+    /// Items whose bounds the ray hits within `max_distance`, nearest first,
+    /// with the distance to the hit.
+    pub fn raycast(&self, origin: Vec2, direction: Vec2, max_distance: f32) -> Vec<(T, f32)> {
+        let direction = direction.normalized();
+        if direction == Vec2::zero() {
+            return vec![];
+        }
+
+        let mut hits: Vec<(usize, f32)> = self
+            .candidates(self.cells_on_ray(origin, direction, max_distance))
+            .into_iter()
+            .filter_map(|index| {
+                ray_rect(origin, direction, self.items[index].1)
+                    .filter(|&distance| distance <= max_distance)
+                    .map(|distance| (index, distance))
+            })
+            .collect();
+        hits.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+        hits.into_iter()
+            .map(|(index, distance)| (self.items[index].0, distance))
+            .collect()
+    }
+
+    /// Cells crossed by the ray, walked one boundary at a time.
+    fn cells_on_ray(&self, origin: Vec2, direction: Vec2, max_distance: f32) -> Vec<(i32, i32)> {
+        let mut cell = self.cell(origin);
+        let step = |d: f32| if d < 0f32 { -1 } else { 1 };
+        // Distance along the ray to the next cell boundary on each axis.
+        let boundary = |position: f32, d: f32, cell: i32| {
+            if d == 0f32 {
+                f32::INFINITY
+            } else {
+                let next = if d < 0f32 { cell } else { cell + 1 } as f32 * self.cell_size;
+                (next - position) / d
+            }
+        };
+        let delta = |d: f32| {
+            if d == 0f32 {
+                f32::INFINITY
+            } else {
+                self.cell_size / d.abs()
+            }
+        };
+
+        let (step_x, step_y) = (step(direction.x), step(direction.y));
+        let (delta_x, delta_y) = (delta(direction.x), delta(direction.y));
+        let mut next_x = boundary(origin.x, direction.x, cell.0);
+        let mut next_y = boundary(origin.y, direction.y, cell.1);
+
+        let mut cells = vec![cell];
+        while next_x.min(next_y) <= max_distance {
+            if next_x < next_y {
+                cell.0 += step_x;
+                next_x += delta_x;
+            } else {
+                cell.1 += step_y;
+                next_y += delta_y;
+            }
+            cells.push(cell);
+        }
+        cells
+    }
+}
+
+impl<T: Copy> Default for SpatialGrid<T> {
+    fn default() -> Self {
+        Self::new(DEFAULT_CELL_SIZE)
+    }
+}
+
+fn overlaps(a: Rect, b: Rect) -> bool {
+    a.left() <= b.right() && b.left() <= a.right() && a.top() <= b.bottom() && b.top() <= a.bottom()
+}
+
+/// Distance along a normalized ray to where it enters `rect`, zero when it
+/// starts inside.
+fn ray_rect(origin: Vec2, direction: Vec2, rect: Rect) -> Option<f32> {
+    let slab = |origin: f32, direction: f32, min: f32, max: f32| {
+        if direction == 0f32 {
+            if origin < min || origin > max {
+                None
+            } else {
+                Some((f32::NEG_INFINITY, f32::INFINITY))
+            }
+        } else {
+            let (t0, t1) = ((min - origin) / direction, (max - origin) / direction);
+            Some((t0.min(t1), t0.max(t1)))
+        }
+    };
+    let (x_enter, x_exit) = slab(origin.x, direction.x, rect.left(), rect.right())?;
+    let (y_enter, y_exit) = slab(origin.y, direction.y, rect.top(), rect.bottom())?;
+    let (enter, exit) = (x_enter.max(y_enter), x_exit.min(y_exit));
+    if enter > exit || exit < 0f32 {
+        None
+    } else {
+        Some(enter.max(0f32))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ggez::graphics::Rect;
+
+    use super::SpatialGrid;
+    use crate::math::Vec2;
+
+    fn square(x: f32, y: f32, size: f32) -> Rect {
+        Rect::new(x, y, size, size)
+    }
+
+    fn grid() -> SpatialGrid<u32> {
+        let mut grid = SpatialGrid::new(100f32);
+        grid.insert(1, square(10f32, 10f32, 20f32));
+        grid.insert(2, square(25f32, 25f32, 20f32));
+        grid.insert(3, square(90f32, 90f32, 30f32));
+        grid.insert(4, square(500f32, 10f32, 20f32));
+        grid.insert(5, square(-150f32, -150f32, 20f32));
+        grid
+    }
+
+    #[test]
+    fn test_query_rect() {
+        let grid = grid();
+        assert_eq!(grid.query_rect(square(0f32, 0f32, 40f32)), vec![1, 2]);
+        assert_eq!(grid.query_rect(square(105f32, 105f32, 5f32)), vec![3]);
+        assert_eq!(grid.query_rect(square(-200f32, -200f32, 60f32)), vec![5]);
+        assert!(grid.query_rect(square(300f32, 300f32, 50f32)).is_empty());
+    }
+
+    #[test]
+    fn test_query_radius() {
+        let grid = grid();
+        assert_eq!(grid.query_radius(Vec2::new(50f32, 50f32), 10f32), vec![2]);
+        assert_eq!(
+            grid.query_radius(Vec2::new(50f32, 50f32), 60f32),
+            vec![1, 2, 3]
+        );
+    }
+
+    #[test]
+    fn test_overlapping_pairs_once() {
+        let mut grid = grid();
+        // Spans several cells together with 3.
+        grid.insert(6, square(80f32, 80f32, 150f32));
+        assert_eq!(grid.overlapping_pairs(), vec![(1, 2), (3, 6)]);
+    }
+
+    #[test]
+    fn test_raycast_sorted_by_distance() {
+        let grid = grid();
+        let hits = grid.raycast(Vec2::new(0f32, 0f32), Vec2::new(1f32, 1f32), 1000f32);
+        let ids: Vec<u32> = hits.iter().map(|hit| hit.0).collect();
+        assert_eq!(ids, vec![1, 2, 3]);
+        assert!((hits[0].1 - 10f32 * 2f32.sqrt()).abs() < 1e-4);
+
+        let hits = grid.raycast(Vec2::new(0f32, 20f32), Vec2::new(1f32, 0f32), 400f32);
+        assert_eq!(hits.iter().map(|hit| hit.0).collect::<Vec<u32>>(), vec![1]);
+
+        let hits = grid.raycast(Vec2::new(0f32, 20f32), Vec2::new(-1f32, 0f32), 400f32);
+        assert!(hits.is_empty());
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut grid = grid();
+        grid.clear();
+        assert!(grid.is_empty());
+        assert!(grid.overlapping_pairs().is_empty());
+        grid.insert(7, square(0f32, 0f32, 10f32));
+        assert_eq!(grid.query_rect(square(0f32, 0f32, 10f32)), vec![7]);
+    }
+}
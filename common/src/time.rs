@@ -0,0 +1,24 @@
+use std::time::Duration;
+
+/// Frame timing handed to systems instead of reading the ggez timer, so a
+/// recorded session can be stepped with its original deltas.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GameTime {
+    delta: Duration,
+    elapsed: Duration,
+}
+
+impl GameTime {
+    pub fn advance(&mut self, delta: Duration) {
+        self.delta = delta;
+        self.elapsed += delta;
+    }
+
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+}
@@ -0,0 +1,163 @@
+use std::str::FromStr;
+
+use ggez::graphics::Rect;
+use ggez::{Context, GameError, GameResult};
+
+use crate::math::Vec2;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ScaleMode {
+    /// Keeps the aspect ratio and fills the leftover window with bars.
+    #[default]
+    Letterbox,
+    /// Fills the whole window, distorting the world when the aspect differs.
+    Stretch,
+}
+
+impl FromStr for ScaleMode {
+    type Err = GameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "letterbox" => Ok(ScaleMode::Letterbox),
+            "stretch" => Ok(ScaleMode::Stretch),
+            _ => Err(GameError::CustomError(format!("Unknown scale mode {}", s))),
+        }
+    }
+}
+
+/// Maps a fixed logical size onto whatever size the window has, so game
+/// code only ever deals with logical coordinates.
+#[derive(Clone, Copy, Debug)]
+pub struct Viewport {
+    pub width: f32,
+    pub height: f32,
+    pub mode: ScaleMode,
+}
+
+impl Viewport {
+    pub fn new(width: f32, height: f32, mode: ScaleMode) -> Self {
+        Self {
+            width,
+            height,
+            mode,
+        }
+    }
+
+    pub fn logical_rect(&self) -> Rect {
+        Rect::new(0f32, 0f32, self.width, self.height)
+    }
+
+    /// Logical area shown by a window of the given size.
+    pub fn screen_coordinates(&self, window: (f32, f32)) -> Rect {
+        let (window_w, window_h) = window;
+        if self.mode == ScaleMode::Stretch || window_w <= 0f32 || window_h <= 0f32 {
+            return self.logical_rect();
+        }
+
+        let scale = (window_w / self.width).min(window_h / self.height);
+        let (visible_w, visible_h) = (window_w / scale, window_h / scale);
+        Rect::new(
+            (self.width - visible_w) / 2f32,
+            (self.height - visible_h) / 2f32,
+            visible_w,
+            visible_h,
+        )
+    }
+
+    /// Parts of the visible area outside the logical rect.
+    pub fn bars(&self, window: (f32, f32)) -> Vec<Rect> {
+        let screen = self.screen_coordinates(window);
+        let mut bars = Vec::new();
+        if screen.x < 0f32 {
+            bars.push(Rect::new(screen.x, screen.y, -screen.x, screen.h));
+            bars.push(Rect::new(self.width, screen.y, -screen.x, screen.h));
+        }
+        if screen.y < 0f32 {
+            bars.push(Rect::new(screen.x, screen.y, screen.w, -screen.y));
+            bars.push(Rect::new(screen.x, self.height, screen.w, -screen.y));
+        }
+        bars
+    }
+
+    /// Points ggez's screen coordinates at the logical area for the current
+    /// window size. Call on startup and on every resize.
+    pub fn apply(&self, ctx: &mut Context) -> GameResult<()> {
+        let window = ggez::graphics::drawable_size(ctx);
+        ggez::graphics::set_screen_coordinates(ctx, self.screen_coordinates(window))
+    }
+}
+
+/// Converts a position in window pixels, as reported by mouse events, into
+/// the current screen coordinates.
+pub fn window_to_screen(ctx: &Context, position: Vec2) -> Vec2 {
+    let (window_w, window_h) = ggez::graphics::drawable_size(ctx);
+    let screen = ggez::graphics::screen_coordinates(ctx);
+    if window_w <= 0f32 || window_h <= 0f32 {
+        return position;
+    }
+    Vec2::new(
+        screen.x + position.x / window_w * screen.w,
+        screen.y + position.y / window_h * screen.h,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use ggez::graphics::Rect;
+
+    use super::{ScaleMode, Viewport};
+
+    #[test]
+    fn test_same_aspect_scales() {
+        let viewport = Viewport::new(1280f32, 720f32, ScaleMode::Letterbox);
+        assert_eq!(
+            viewport.screen_coordinates((1920f32, 1080f32)),
+            Rect::new(0f32, 0f32, 1280f32, 720f32)
+        );
+        assert!(viewport.bars((1920f32, 1080f32)).is_empty());
+    }
+
+    #[test]
+    fn test_letterbox_taller_window() {
+        let viewport = Viewport::new(1280f32, 720f32, ScaleMode::Letterbox);
+        let window = (640f32, 640f32);
+
+        assert_eq!(
+            viewport.screen_coordinates(window),
+            Rect::new(0f32, -280f32, 1280f32, 1280f32)
+        );
+        assert_eq!(
+            viewport.bars(window),
+            vec![
+                Rect::new(0f32, -280f32, 1280f32, 280f32),
+                Rect::new(0f32, 720f32, 1280f32, 280f32),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_letterbox_wider_window() {
+        let viewport = Viewport::new(100f32, 100f32, ScaleMode::Letterbox);
+        assert_eq!(
+            viewport.screen_coordinates((400f32, 200f32)),
+            Rect::new(-50f32, 0f32, 200f32, 100f32)
+        );
+    }
+
+    #[test]
+    fn test_stretch_ignores_aspect() {
+        let viewport = Viewport::new(1280f32, 720f32, ScaleMode::Stretch);
+        assert_eq!(
+            viewport.screen_coordinates((640f32, 640f32)),
+            Rect::new(0f32, 0f32, 1280f32, 720f32)
+        );
+        assert!(viewport.bars((640f32, 640f32)).is_empty());
+    }
+
+    #[test]
+    fn test_parse_mode() {
+        assert_eq!("stretch".parse::<ScaleMode>().unwrap(), ScaleMode::Stretch);
+        assert!("zoom".parse::<ScaleMode>().is_err());
+    }
+}
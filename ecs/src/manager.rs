@@ -1,16 +1,21 @@
 use hashbrown::{HashMap, HashSet};
 use std::any::{Any, TypeId};
 use std::borrow::Borrow;
-use std::hash::Hash;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{BuildHasher, BuildHasherDefault, Hash};
 
 use crate::entity::{Entity, EntityId};
 use crate::type_query::TypesQueryable;
 
+/// Unseeded hasher so iteration order only depends on what was inserted and
+/// a replayed session visits entities in the same order as the recording.
+type FixedState = BuildHasherDefault<DefaultHasher>;
+
 #[derive(Default)]
 pub struct EntityManager {
-    entities: HashMap<EntityId, Entity>,
-    component_index: HashMap<Vec<TypeId>, HashSet<EntityId>>,
-    pending_add: HashMap<EntityId, Entity>,
+    entities: HashMap<EntityId, Entity, FixedState>,
+    component_index: HashMap<Vec<TypeId>, HashSet<EntityId, FixedState>, FixedState>,
+    pending_add: HashMap<EntityId, Entity, FixedState>,
     size: u64,
 }
 
@@ -163,11 +168,12 @@ impl EntityManager {
     }
 }
 
-fn get_or_insert<K, V, BK>(key: BK, map: &mut HashMap<K, V>) -> &mut V
+fn get_or_insert<K, V, BK, S>(key: BK, map: &mut HashMap<K, V, S>) -> &mut V
 where
     K: Eq + Hash + Clone,
     BK: Borrow<K>,
     V: Default,
+    S: BuildHasher,
 {
     let b_key = key.borrow();
     if !map.contains_key(b_key) {
@@ -1,44 +1,172 @@
-use crate::space_shooter::tag;
+use crate::scene::{Scene, Transition};
+use crate::space_shooter::{tag, GameOptions};
+use common::event::{EventReceiver, EventSystem};
+use ecs::entity::EntityId;
 use ecs::manager::EntityManager;
-use ggez::event::EventHandler;
-use ggez::graphics::Color;
-use ggez::GameError;
+use ggez::{Context, GameResult};
+use std::path::PathBuf;
 
-use crate::ui::{render_ui_system, Button};
+use crate::ui::{
+    add_widget, render_ui_system, ui_interaction_system, Anchor, Button, Checkbox, Direction,
+    Label, Slider, Stack, TextInput, Ui, UiEvent, UiNode, Widget,
+};
 
+const DEFAULT_RECORD_PATH: &str = "session.replay";
+
+/// Main menu, the first scene on the stack.
 #[derive(Default)]
 pub struct Game {
     entity_manager: EntityManager,
-    setup: bool,
+    event_system: EventSystem,
+    ui: Ui,
+    options: GameOptions,
+    record_path: PathBuf,
+    space_game_btn: EntityId,
+    quit_btn: EntityId,
+    seed_input: EntityId,
+    dead_zone_slider: EntityId,
+    record_checkbox: EntityId,
 }
 
 impl Game {
-    fn setup(&mut self) {
-        self.setup = true;
-        let space_game_btn = self.entity_manager.add();
-        space_game_btn
-            .add_component(Button {
-                title: "Space Game".to_string(),
-                size: 50f32,
+    pub fn new(options: GameOptions) -> Self {
+        let record_path = options
+            .record
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_RECORD_PATH));
+        Self {
+            options,
+            record_path,
+            ..Default::default()
+        }
+    }
+
+    fn add_stack(&mut self, parent: Option<EntityId>, direction: Direction) -> EntityId {
+        let node = UiNode::new(Anchor::Center);
+        self.entity_manager
+            .add_tag(tag::Ui)
+            .add_component(match parent {
+                Some(parent) => node.with_parent(parent),
+                None => node,
+            })
+            .add_component(Stack {
+                direction,
+                spacing: 10f32,
             })
-            .add_component(tag::Ui);
+            .id
     }
-}
 
-impl EventHandler for Game {
-    fn update(&mut self, _ctx: &mut ggez::Context) -> Result<(), GameError> {
-        if !self.setup {
-            self.setup();
+    fn set_seed(&mut self, text: &str) {
+        let seed = text.parse().ok();
+        self.options.seed = seed;
+        let valid = seed.is_some() || text.is_empty();
+        if let Some(widget) = self
+            .entity_manager
+            .get_entity(self.space_game_btn)
+            .and_then(|entity| entity.get_component_mut::<Widget>())
+        {
+            widget.disabled = !valid;
         }
+    }
+}
+
+impl Scene for Game {
+    fn update(&mut self, ctx: &mut Context) -> GameResult<Transition> {
         self.entity_manager.update();
-        Ok(())
+        ui_interaction_system(
+            &mut self.entity_manager,
+            &mut self.ui,
+            ctx,
+            &mut self.event_system,
+        )?;
+
+        let events: Vec<UiEvent> = self.event_system.read();
+        for event in events {
+            match event {
+                UiEvent::Clicked(id) if id == self.space_game_btn => {
+                    return Ok(Transition::Switch(Box::new(self.options.new_game())));
+                }
+                UiEvent::Clicked(id) if id == self.quit_btn => return Ok(Transition::Pop),
+                UiEvent::TextChanged(id, text) if id == self.seed_input => self.set_seed(&text),
+                UiEvent::ValueChanged(id, value) if id == self.dead_zone_slider => {
+                    self.options.dead_zone = value;
+                }
+                UiEvent::Toggled(id, checked) if id == self.record_checkbox => {
+                    self.options.record = checked.then(|| self.record_path.clone());
+                }
+                _ => {}
+            }
+        }
+        Ok(Transition::None)
     }
 
-    fn draw(&mut self, ctx: &mut ggez::Context) -> Result<(), GameError> {
-        ggez::graphics::clear(ctx, Color::WHITE);
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        render_ui_system(&mut self.entity_manager, ctx)
+    }
 
-        render_ui_system(&mut self.entity_manager, ctx)?;
+    fn ui(&mut self) -> Option<&mut Ui> {
+        Some(&mut self.ui)
+    }
 
-        ggez::graphics::present(ctx)
+    fn on_enter(&mut self, _ctx: &mut Context) -> GameResult<()> {
+        let menu = self.add_stack(None, Direction::Vertical);
+        add_widget(
+            &mut self.entity_manager,
+            menu,
+            Label {
+                text: "COMP4300".to_string(),
+                size: 60f32,
+            },
+        );
+        self.seed_input = add_widget(
+            &mut self.entity_manager,
+            menu,
+            TextInput {
+                text: self.options.seed.map(|s| s.to_string()).unwrap_or_default(),
+                placeholder: "Random seed".to_string(),
+                size: 25f32,
+                max_len: 20,
+            },
+        );
+        self.dead_zone_slider = add_widget(
+            &mut self.entity_manager,
+            menu,
+            Slider {
+                label: "Stick dead zone".to_string(),
+                size: 25f32,
+                value: self.options.dead_zone,
+                min: 0f32,
+                max: 0.5,
+                step: 0.05,
+            },
+        );
+        self.record_checkbox = add_widget(
+            &mut self.entity_manager,
+            menu,
+            Checkbox {
+                label: format!("Record to {}", self.record_path.display()),
+                size: 25f32,
+                checked: self.options.record.is_some(),
+            },
+        );
+        let buttons = self.add_stack(Some(menu), Direction::Horizontal);
+        self.space_game_btn = add_widget(
+            &mut self.entity_manager,
+            buttons,
+            Button {
+                title: "Space Game".to_string(),
+                size: 50f32,
+            },
+        );
+        self.quit_btn = add_widget(
+            &mut self.entity_manager,
+            buttons,
+            Button {
+                title: "Quit".to_string(),
+                size: 30f32,
+            },
+        );
+        self.ui.set_focus(Some(self.space_game_btn));
+        Ok(())
     }
 }
@@ -1,17 +1,72 @@
-use crate::space_shooter::SpaceGame;
-use ggez::{conf::WindowMode, ContextBuilder, GameResult};
+use crate::game::Game;
+use crate::scene::{Scene, SceneStack};
+use crate::space_shooter::{GameOptions, SpaceGame};
+use common::replay::Replay;
+use common::view::{ScaleMode, Viewport};
+use ggez::{conf::WindowMode, ContextBuilder, GameError, GameResult};
+use std::path::PathBuf;
 
 mod game;
+mod scene;
 mod space_shooter;
 mod ui;
 
-const WINDOWS_WIDTH: f32 = 1280f32;
-const WINDOWS_HEIGHT: f32 = 720f32;
+/// Size of the screen in game coordinates, independent of the window size.
+const LOGICAL_WIDTH: f32 = 1280f32;
+const LOGICAL_HEIGHT: f32 = 720f32;
+
+fn flag_value(args: &[String], flag: &str) -> GameResult<Option<String>> {
+    match args.iter().position(|arg| arg == flag) {
+        None => Ok(None),
+        Some(idx) => args
+            .get(idx + 1)
+            .cloned()
+            .map(Some)
+            .ok_or_else(|| GameError::CustomError(format!("{} requires a value", flag))),
+    }
+}
+
+/// Replays start straight in the game, everything else opens the menu.
+fn first_scene(args: &[String]) -> GameResult<Box<dyn Scene>> {
+    if let Some(path) = flag_value(args, "--replay")? {
+        let replay = Replay::load(&path)?;
+        return Ok(Box::new(SpaceGame::from_replay(replay)));
+    }
+
+    let seed = match flag_value(args, "--seed")? {
+        Some(value) => Some(
+            value
+                .parse()
+                .map_err(|_| GameError::CustomError(format!("Invalid seed {}", value)))?,
+        ),
+        None => None,
+    };
+    let options = GameOptions {
+        seed,
+        record: flag_value(args, "--record")?.map(PathBuf::from),
+        ..Default::default()
+    };
+    Ok(Box::new(Game::new(options)))
+}
 
 fn main() -> GameResult<()> {
-    let (ctx, event_loop) = ContextBuilder::new("Comp4300", "Boss")
-        .window_mode(WindowMode::default().dimensions(WINDOWS_WIDTH, WINDOWS_HEIGHT))
-        .build()?;
+    let args: Vec<String> = std::env::args().collect();
+    let scale_mode = match flag_value(&args, "--scale")? {
+        Some(value) => value.parse()?,
+        None => ScaleMode::default(),
+    };
+    let viewport = Viewport::new(LOGICAL_WIDTH, LOGICAL_HEIGHT, scale_mode);
+    let scenes = SceneStack::new(first_scene(&args)?, viewport);
+
+    let window_mode = WindowMode::default()
+        .dimensions(LOGICAL_WIDTH, LOGICAL_HEIGHT)
+        .resizable(true);
+    let mut context_builder = ContextBuilder::new("Comp4300", "Boss").window_mode(window_mode);
+    if let Ok(manifest_dir) = std::env::var("CARGO_MANIFEST_DIR") {
+        context_builder =
+            context_builder.add_resource_path(PathBuf::from(manifest_dir).join("resources"));
+    }
+    let (ctx, event_loop) = context_builder.build()?;
 
-    ggez::event::run(ctx, event_loop, SpaceGame::default())
+    ggez::event::run(ctx, event_loop, scenes)
 }
@@ -0,0 +1,266 @@
+use common::math::Vec2;
+use common::view::{window_to_screen, Viewport};
+use ggez::conf::FullscreenType;
+use ggez::event::{Axis, Button, EventHandler, KeyCode, KeyMods, MouseButton};
+use ggez::graphics::{Color, DrawMode, MeshBuilder};
+use ggez::input::gamepad::GamepadId;
+use ggez::{Context, GameError, GameResult};
+
+use crate::ui::Ui;
+
+pub enum Transition {
+    None,
+    Push(Box<dyn Scene>),
+    Pop,
+    Switch(Box<dyn Scene>),
+    /// Exits every scene on the stack and starts over from the given one.
+    Reset(Box<dyn Scene>),
+}
+
+/// A game state on the `SceneStack`. Only the top scene is updated and
+/// receives keyboard and mouse input, so everything below it is frozen.
+pub trait Scene {
+    fn update(&mut self, ctx: &mut Context) -> GameResult<Transition>;
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()>;
+
+    /// Overlays are drawn on top of the scene below instead of replacing it.
+    fn is_overlay(&self) -> bool {
+        false
+    }
+
+    /// Widgets of the scene. The stack feeds it pointer, keyboard and gamepad
+    /// input while the scene is on top.
+    fn ui(&mut self) -> Option<&mut Ui> {
+        None
+    }
+
+    fn on_enter(&mut self, _ctx: &mut Context) -> GameResult<()> {
+        Ok(())
+    }
+
+    fn on_exit(&mut self, _ctx: &mut Context) -> GameResult<()> {
+        Ok(())
+    }
+
+    fn key_down_event(&mut self, _ctx: &mut Context, _keycode: KeyCode, _repeat: bool) {}
+
+    fn mouse_button_down_event(
+        &mut self,
+        _ctx: &mut Context,
+        _button: MouseButton,
+        _x: f32,
+        _y: f32,
+    ) {
+    }
+
+    fn gamepad_button_down_event(&mut self, _ctx: &mut Context, _btn: Button) {}
+
+    fn gamepad_button_up_event(&mut self, _ctx: &mut Context, _btn: Button) {}
+
+    fn gamepad_axis_event(&mut self, _ctx: &mut Context, _axis: Axis, _value: f32) {}
+}
+
+pub struct SceneStack {
+    scenes: Vec<Box<dyn Scene>>,
+    viewport: Viewport,
+    fullscreen: bool,
+    started: bool,
+}
+
+impl SceneStack {
+    pub fn new(initial: Box<dyn Scene>, viewport: Viewport) -> Self {
+        Self {
+            scenes: vec![initial],
+            viewport,
+            fullscreen: false,
+            started: false,
+        }
+    }
+
+    fn toggle_fullscreen(&mut self, ctx: &mut Context) -> GameResult<()> {
+        self.fullscreen = !self.fullscreen;
+        let fullscreen_type = if self.fullscreen {
+            FullscreenType::Desktop
+        } else {
+            FullscreenType::Windowed
+        };
+        ggez::graphics::set_fullscreen(ctx, fullscreen_type)?;
+        self.viewport.apply(ctx)
+    }
+
+    fn draw_bars(&self, ctx: &mut Context) -> GameResult<()> {
+        let bars = self.viewport.bars(ggez::graphics::drawable_size(ctx));
+        if bars.is_empty() {
+            return Ok(());
+        }
+        let mut mesh = MeshBuilder::new();
+        for bar in bars {
+            mesh.rectangle(DrawMode::fill(), bar, Color::BLACK)?;
+        }
+        let mesh = mesh.build(ctx)?;
+        ggez::graphics::draw(ctx, &mesh, ([0f32, 0f32], Color::WHITE))
+    }
+
+    fn push(&mut self, ctx: &mut Context, mut scene: Box<dyn Scene>) -> GameResult<()> {
+        scene.on_enter(ctx)?;
+        self.scenes.push(scene);
+        Ok(())
+    }
+
+    fn pop(&mut self, ctx: &mut Context) -> GameResult<()> {
+        if let Some(mut scene) = self.scenes.pop() {
+            scene.on_exit(ctx)?;
+        }
+        Ok(())
+    }
+
+    fn apply(&mut self, ctx: &mut Context, transition: Transition) -> GameResult<()> {
+        match transition {
+            Transition::None => {}
+            Transition::Push(scene) => self.push(ctx, scene)?,
+            Transition::Pop => self.pop(ctx)?,
+            Transition::Switch(scene) => {
+                self.pop(ctx)?;
+                self.push(ctx, scene)?;
+            }
+            Transition::Reset(scene) => {
+                while !self.scenes.is_empty() {
+                    self.pop(ctx)?;
+                }
+                self.push(ctx, scene)?;
+            }
+        }
+
+        if self.scenes.is_empty() {
+            ggez::event::quit(ctx);
+        }
+        Ok(())
+    }
+}
+
+impl EventHandler for SceneStack {
+    fn update(&mut self, ctx: &mut Context) -> Result<(), GameError> {
+        if !self.started {
+            self.started = true;
+            self.viewport.apply(ctx)?;
+            for scene in self.scenes.iter_mut() {
+                scene.on_enter(ctx)?;
+            }
+        }
+
+        let transition = match self.scenes.last_mut() {
+            Some(scene) => scene.update(ctx)?,
+            None => Transition::None,
+        };
+        self.apply(ctx, transition)
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> Result<(), GameError> {
+        ggez::graphics::clear(ctx, Color::WHITE);
+
+        let first_visible = self
+            .scenes
+            .iter()
+            .rposition(|scene| !scene.is_overlay())
+            .unwrap_or(0);
+        for scene in self.scenes[first_visible..].iter_mut() {
+            scene.draw(ctx)?;
+        }
+        self.draw_bars(ctx)?;
+
+        ggez::graphics::present(ctx)?;
+        ggez::timer::yield_now();
+        Ok(())
+    }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        keycode: KeyCode,
+        keymods: KeyMods,
+        repeat: bool,
+    ) {
+        let alt_enter = keycode == KeyCode::Return && keymods.contains(KeyMods::ALT);
+        if !repeat && (keycode == KeyCode::F11 || alt_enter) {
+            if let Err(e) = self.toggle_fullscreen(ctx) {
+                eprintln!("Failed to toggle fullscreen: {}", e);
+            }
+            return;
+        }
+        if let Some(scene) = self.scenes.last_mut() {
+            if let Some(ui) = scene.ui() {
+                ui.key_down(keycode, keymods, repeat);
+            }
+            scene.key_down_event(ctx, keycode, repeat);
+        }
+    }
+
+    fn text_input_event(&mut self, _ctx: &mut Context, character: char) {
+        if let Some(ui) = self.scenes.last_mut().and_then(|scene| scene.ui()) {
+            ui.text_input(character);
+        }
+    }
+
+    // Mouse events arrive in window pixels, scenes get logical coordinates.
+    fn mouse_button_down_event(&mut self, ctx: &mut Context, button: MouseButton, x: f32, y: f32) {
+        let Vec2 { x, y } = window_to_screen(ctx, Vec2::new(x, y));
+        if let Some(scene) = self.scenes.last_mut() {
+            if let Some(ui) = scene.ui() {
+                ui.mouse_button_down(button, x, y);
+            }
+            scene.mouse_button_down_event(ctx, button, x, y);
+        }
+    }
+
+    fn mouse_button_up_event(&mut self, ctx: &mut Context, button: MouseButton, x: f32, y: f32) {
+        let Vec2 { x, y } = window_to_screen(ctx, Vec2::new(x, y));
+        if let Some(ui) = self.scenes.last_mut().and_then(|scene| scene.ui()) {
+            ui.mouse_button_up(button, x, y);
+        }
+    }
+
+    fn mouse_motion_event(&mut self, ctx: &mut Context, x: f32, y: f32, _dx: f32, _dy: f32) {
+        let Vec2 { x, y } = window_to_screen(ctx, Vec2::new(x, y));
+        if let Some(ui) = self.scenes.last_mut().and_then(|scene| scene.ui()) {
+            ui.mouse_motion(x, y);
+        }
+    }
+
+    // Gamepad events reach every scene so covered scenes still know which
+    // buttons are held once they are back on top.
+    fn gamepad_button_down_event(&mut self, ctx: &mut Context, btn: Button, _id: GamepadId) {
+        if let Some(ui) = self.scenes.last_mut().and_then(|scene| scene.ui()) {
+            ui.gamepad_button_down(btn);
+        }
+        for scene in self.scenes.iter_mut() {
+            scene.gamepad_button_down_event(ctx, btn);
+        }
+    }
+
+    fn gamepad_button_up_event(&mut self, ctx: &mut Context, btn: Button, _id: GamepadId) {
+        for scene in self.scenes.iter_mut() {
+            scene.gamepad_button_up_event(ctx, btn);
+        }
+    }
+
+    fn gamepad_axis_event(&mut self, ctx: &mut Context, axis: Axis, value: f32, _id: GamepadId) {
+        for scene in self.scenes.iter_mut() {
+            scene.gamepad_axis_event(ctx, axis, value);
+        }
+    }
+
+    fn resize_event(&mut self, ctx: &mut Context, _width: f32, _height: f32) {
+        if let Err(e) = self.viewport.apply(ctx) {
+            eprintln!("Failed to resize viewport: {}", e);
+        }
+    }
+
+    fn quit_event(&mut self, ctx: &mut Context) -> bool {
+        while let Some(mut scene) = self.scenes.pop() {
+            if let Err(e) = scene.on_exit(ctx) {
+                eprintln!("Failed to exit scene: {}", e);
+            }
+        }
+        false
+    }
+}
@@ -1,3 +1,4 @@
+use ecs::entity::EntityId;
 use std::time::Duration;
 
 pub struct Spawner {
@@ -10,6 +11,18 @@ pub struct Scoreboard {
     pub current_score: i32,
 }
 
+/// Lives the player has left, and when it comes back after losing one.
+pub struct Lives {
+    pub left: u32,
+    pub respawn: Option<Respawn>,
+}
+
+#[derive(Clone, Copy)]
+pub struct Respawn {
+    pub at: Duration,
+    pub arena: EntityId,
+}
+
 #[derive(Clone)]
 pub struct DisplayTextEvent {
     pub text: String,
@@ -25,5 +38,4 @@ pub struct DisplayText {
 pub struct CacheDisplayText {
     pub raw_text: String,
     pub text: ggez::graphics::Text,
-    pub position: [f32; 2],
 }
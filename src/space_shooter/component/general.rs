@@ -9,7 +9,65 @@ pub struct Lifespan {
 
 #[derive(Copy, Clone)]
 pub struct SpeedBoost {
-    pub is_boosting: bool,
     pub last_boost: Option<Duration>,
+}
+
+pub struct Health {
+    pub current: i32,
+    pub max: i32,
+}
+
+impl Health {
+    pub fn new(max: i32) -> Self {
+        Self { current: max, max }
+    }
+
+    pub fn take(&mut self, damage: i32) {
+        self.current = (self.current - damage).clamp(0, self.max);
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.current <= 0
+    }
+}
+
+/// Damage dealt to whatever the entity hits.
+#[derive(Clone, Copy)]
+pub struct Damage(pub i32);
+
+/// Hits are ignored while `time_left` runs, each hit taken starts `grace`
+/// over.
+pub struct Invulnerability {
+    pub grace: Duration,
     pub time_left: Duration,
 }
+
+impl Invulnerability {
+    pub fn active(&self) -> bool {
+        self.time_left > Duration::ZERO
+    }
+}
+
+/// Drawn lit up for `time_left` after a hit.
+#[derive(Default)]
+pub struct HitFlash {
+    pub time_left: Duration,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Health;
+
+    #[test]
+    fn test_health_take() {
+        let mut health = Health::new(3);
+        health.take(1);
+        assert_eq!(health.current, 2);
+        assert!(!health.is_dead());
+        health.take(5);
+        assert_eq!(health.current, 0);
+        assert!(health.is_dead());
+        health.take(-10);
+        assert_eq!(health.current, 3);
+    }
+}
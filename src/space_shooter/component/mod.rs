@@ -1,21 +1,32 @@
-use crate::space_shooter::component::general::{Lifespan, Score, SpeedBoost};
-use crate::space_shooter::component::movement::Speed;
+use crate::space_shooter::component::general::{
+    Damage, Health, HitFlash, Invulnerability, Lifespan, Score, SpeedBoost,
+};
+use crate::space_shooter::component::movement::{RigidBody, Speed};
 use crate::space_shooter::component::shape::{Geometry, Shape};
 use crate::space_shooter::tag;
-use crate::{WINDOWS_HEIGHT, WINDOWS_WIDTH};
+use crate::ui::{Anchor, Direction, Margin, Stack, UiNode};
 use common::game_transform::GameTransform;
 use common::math::Vec2;
-use ecs::entity::Entity;
+use ecs::entity::{Entity, EntityId};
 use ecs::manager::EntityManager;
+use ggez::graphics::Rect;
 use std::time::Duration;
 
 use crate::space_shooter::component::constant::{
-    BULLET_LIFESPAN, BULLET_SIZE, BULLET_SPAWN_INTERVAL, ENEMY_MAX_SPEED, ENEMY_MIN_SPEED,
-    ENEMY_SIZE, ENEMY_SPAWN_INTERVAL, MAX_ENEMY_SPAWN,
+    BULLET_SPAWN_INTERVAL, ENEMY_CONTACT_DAMAGE, ENEMY_FIRE_INTERVAL, ENEMY_MAX_SIDES,
+    ENEMY_MAX_SPEED, ENEMY_MAX_SPIN, ENEMY_MIN_SIDES, ENEMY_MIN_SPEED, ENEMY_SCORE, ENEMY_SIZE,
+    ENEMY_SPAWN_INTERVAL, ENEMY_SPREAD, MAX_ENEMY_SPAWN, OBSTACLE_SIZE, PLAYER_DRAG, PLAYER_GRACE,
+    PLAYER_HEALTH, PLAYER_LIVES, PLAYER_RAM_DAMAGE, PLAYER_SPEED, SMALL_ENEMY_LIFESPAN,
+};
+use crate::space_shooter::component::game::{DisplayText, Lives, Spawner};
+use crate::space_shooter::component::physics::{
+    layer, Arena, Collider, Confined, FastMoving, Solid,
+};
+use crate::space_shooter::component::weapon::{
+    FireMode, Homing, Projectile, ProjectileTemplate, SpecialWeapon, Weapon, WeaponDef,
+    ENEMY_BULLET, PLAYER_BULLET,
 };
-use crate::space_shooter::component::game::{DisplayText, Spawner};
-use crate::space_shooter::component::physics::Collider;
-use common::math::random::rand_element;
+use common::math::random::{rand_element, GameRng, RandomStream};
 use rand::Rng;
 
 use self::game::Scoreboard;
@@ -25,50 +36,126 @@ pub mod general;
 pub mod movement;
 pub mod physics;
 pub mod shape;
+pub mod weapon;
 
 pub(crate) mod constant {
     use std::time::Duration;
 
     pub const PLAYER_SPEED: f32 = 300f32;
+    pub const PLAYER_THRUST: f32 = 1800f32;
+    pub const PLAYER_DRAG: f32 = 5f32;
+    pub const PLAYER_BOOST_IMPULSE: f32 = 900f32;
+    pub const BOOST_COOL_DOWN: Duration = Duration::from_secs(3);
+    pub const PLAYER_HEALTH: i32 = 3;
+    pub const PLAYER_RAM_DAMAGE: i32 = 1;
+    /// Time the player shrugs off hits after taking one.
+    pub const PLAYER_GRACE: Duration = Duration::from_millis(1500);
+    pub const PLAYER_LIVES: u32 = 3;
+    pub const RESPAWN_DELAY: Duration = Duration::from_secs(2);
+    /// Distance a respawning player keeps from every enemy.
+    pub const SAFE_SPAWN_DISTANCE: f32 = 250f32;
+    pub const HIT_FLASH_TIME: Duration = Duration::from_millis(100);
+
+    pub const ARENA_WIDTH: f32 = 3200f32;
+    pub const ARENA_HEIGHT: f32 = 1800f32;
 
     pub const BULLET_SIZE: f32 = 12f32;
     pub const BULLET_SPEED: f32 = 400f32;
     pub const BULLET_LIFESPAN: Duration = Duration::from_secs(2);
     pub const BULLET_SPAWN_INTERVAL: Duration = Duration::from_millis(300);
+    pub const LASER_RANGE: f32 = 600f32;
+
+    pub const ENEMY_BULLET_SIZE: f32 = 8f32;
+    pub const ENEMY_BULLET_SPEED: f32 = 250f32;
+    pub const ENEMY_FIRE_INTERVAL: Duration = Duration::from_secs(2);
+    pub const ENEMY_FIRE_RANGE: f32 = 400f32;
+    /// Radians a polygon enemy's fan covers.
+    pub const ENEMY_SPREAD: f32 = 1f32;
 
     pub const ENEMY_MIN_SPEED: f32 = 100f32;
     pub const ENEMY_MAX_SPEED: f32 = 200f32;
     pub const ENEMY_SIZE: f32 = 32f32;
+    /// Radians per second.
+    pub const ENEMY_MAX_SPIN: f32 = 3f32;
+    pub const ENEMY_MIN_SIDES: u32 = 3;
+    pub const ENEMY_MAX_SIDES: u32 = 8;
+    pub const ENEMY_SCORE: i32 = 100;
+    pub const ENEMY_CONTACT_DAMAGE: i32 = 1;
+    pub const CIRCLE_VERTICES: u32 = 8;
+
+    pub const SMALL_ENEMY_SPEED: f32 = 150f32;
+    pub const SMALL_ENEMY_LIFESPAN: Duration = Duration::from_millis(1500);
+
+    pub const OBSTACLE_SIZE: f32 = 64f32;
 
     pub const MAX_ENEMY_SPAWN: usize = 32;
     pub const ENEMY_SPAWN_INTERVAL: Duration = Duration::from_secs(3);
+
+    pub const CAMERA_FOLLOW_SPEED: f32 = 4f32;
+    pub const ENEMY_KILLED_TRAUMA: f32 = 0.3;
+    pub const PLAYER_DIED_TRAUMA: f32 = 0.8;
 }
 
-pub fn create_bullet(
-    manager: &mut EntityManager,
-    speed: Speed,
+/// Projectile made from `template`, flying the way `transform` faces.
+pub fn create_projectile<'m>(
+    manager: &'m mut EntityManager,
+    template: &ProjectileTemplate,
     transform: GameTransform,
-) -> &Entity {
-    manager
+) -> &'m Entity {
+    let velocity = Vec2::from_angle(transform.rotation) * template.speed;
+    let entity = manager
         .add()
         .add_component(tag::Bullet)
         .add_component(Shape {
-            geometry: Geometry::Circle,
-            radius: BULLET_SIZE,
+            geometry: template.geometry,
+            radius: template.radius,
         })
         .add_component(Collider {
             center: transform.position,
-            radius: BULLET_SIZE,
+            radius: template.radius,
+            rotation: transform.rotation,
+            layer: template.layer,
+            mask: template.mask,
+            trigger: true,
         })
-        .add_component(speed)
+        .add_component(FastMoving)
+        .add_component(Speed { velocity })
+        .add_component(RigidBody::new(1f32))
         .add_component(transform)
         .add_component(Lifespan {
-            time_left: BULLET_LIFESPAN,
-            total_time: BULLET_LIFESPAN,
+            time_left: template.lifespan,
+            total_time: template.lifespan,
+        })
+        .add_component(Projectile {
+            pierce: template.pierce,
         })
+        .add_component(Damage(template.damage));
+    if template.homing > 0f32 {
+        entity.add_component(Homing {
+            turn_rate: template.homing,
+        })
+    } else {
+        entity
+    }
 }
 
-pub fn create_player(manager: &mut EntityManager) -> &Entity {
+pub fn create_arena(manager: &mut EntityManager, bounds: Rect) -> &Entity {
+    manager.add().add_component(Arena { bounds })
+}
+
+/// Every arena with its id, in creation order.
+pub fn arenas(manager: &mut EntityManager) -> Vec<(EntityId, Rect)> {
+    let mut arenas: Vec<(EntityId, Rect)> = manager
+        .query_entities_component_mut::<Arena>()
+        .into_iter()
+        .map(|(id, arena)| (id, arena.bounds))
+        .collect();
+    arenas.sort_by_key(|(id, _)| *id);
+    arenas
+}
+
+/// Player at `position`, shrugging off hits for a moment after it appears.
+pub fn create_player(manager: &mut EntityManager, arena: EntityId, position: Vec2) -> &Entity {
     manager
         .add()
         .add_component(tag::Player)
@@ -76,27 +163,71 @@ pub fn create_player(manager: &mut EntityManager) -> &Entity {
             geometry: Geometry::Rectangle,
             radius: 32f32,
         })
-        .add_component(GameTransform::new(
-            Vec2::new(WINDOWS_WIDTH / 2f32 - 32f32, WINDOWS_HEIGHT / 2f32 - 32f32),
-            Vec2::zero(),
-        ))
+        .add_component(GameTransform::new(position))
         .add_component(Collider {
-            center: Vec2::new(WINDOWS_WIDTH / 2f32 - 32f32, WINDOWS_HEIGHT / 2f32 - 32f32),
+            center: position,
             radius: 32f32,
+            rotation: 0f32,
+            layer: layer::PLAYER,
+            mask: layer::ENEMY | layer::OBSTACLE,
+            trigger: false,
         })
-        .add_component(SpeedBoost {
-            is_boosting: false,
-            time_left: Duration::default(),
-            last_boost: None,
+        .add_component(Confined(arena))
+        .add_component(Solid::Dynamic)
+        .add_component(Speed {
+            velocity: Vec2::zero(),
+        })
+        .add_component(
+            RigidBody::new(1f32)
+                .with_drag(PLAYER_DRAG)
+                .with_max_speed(PLAYER_SPEED),
+        )
+        .add_component(SpeedBoost { last_boost: None })
+        .add_component(Health::new(PLAYER_HEALTH))
+        .add_component(Damage(PLAYER_RAM_DAMAGE))
+        .add_component(Invulnerability {
+            grace: PLAYER_GRACE,
+            time_left: PLAYER_GRACE,
         })
+        .add_component(HitFlash::default())
+        .add_component(Weapon::new(
+            FireMode::Automatic,
+            PLAYER_BULLET,
+            BULLET_SPAWN_INTERVAL,
+        ))
+}
+
+/// Random coordinate an enemy fits at between `start` and `end`, the middle
+/// when the arena is too small to fit one.
+fn spawn_coordinate(rng: &mut impl Rng, start: f32, end: f32) -> f32 {
+    let (low, high) = (start + ENEMY_SIZE, end - ENEMY_SIZE);
+    if low <= high {
+        rng.gen_range(low..=high)
+    } else {
+        (start + end) / 2f32
+    }
 }
 
-pub fn create_enemy(manager: &mut EntityManager) -> &Entity {
-    let mut rng = rand::thread_rng();
+pub fn create_enemy<'m>(
+    manager: &'m mut EntityManager,
+    rng: &mut GameRng,
+    arena: EntityId,
+    bounds: Rect,
+) -> &'m Entity {
+    let rng = rng.stream(RandomStream::Spawn);
     let speed = rng.gen_range(ENEMY_MIN_SPEED..=ENEMY_MAX_SPEED);
-    let x_pos = rng.gen_range(0f32..=(WINDOWS_WIDTH - ENEMY_SIZE));
-    let y_pos = rng.gen_range(0f32..=(WINDOWS_HEIGHT - ENEMY_SIZE));
-    let shape = rand_element([Geometry::Rectangle, Geometry::Circle]);
+    let x_pos = spawn_coordinate(rng, bounds.left(), bounds.right());
+    let y_pos = spawn_coordinate(rng, bounds.top(), bounds.bottom());
+    let sides = rng.gen_range(ENEMY_MIN_SIDES..=ENEMY_MAX_SIDES);
+    let shape = rand_element(
+        rng,
+        [
+            Geometry::Rectangle,
+            Geometry::Circle,
+            Geometry::Polygon { sides },
+        ],
+    );
+    let spin = rng.gen_range(-ENEMY_MAX_SPIN..=ENEMY_MAX_SPIN);
     manager
         .add()
         .add_component(tag::Enemy)
@@ -104,15 +235,133 @@ pub fn create_enemy(manager: &mut EntityManager) -> &Entity {
             geometry: shape,
             radius: ENEMY_SIZE,
         })
-        .add_component(GameTransform::new(Vec2::new(x_pos, y_pos), Vec2::zero()))
-        .add_component(Score(100))
+        .add_component(GameTransform::new(Vec2::new(x_pos, y_pos)))
+        .add_component(Score(ENEMY_SCORE))
         .add_component(Speed {
             velocity: Vec2::new(speed, speed),
         })
+        .add_component(RigidBody::new(1f32).with_angular_velocity(spin))
         .add_component(Collider {
             center: Vec2::new(x_pos, y_pos),
             radius: ENEMY_SIZE,
+            rotation: 0f32,
+            layer: layer::ENEMY,
+            mask: layer::PLAYER | layer::ENEMY | layer::BULLET | layer::OBSTACLE,
+            trigger: false,
         })
+        .add_component(Confined(arena))
+        .add_component(Solid::Dynamic)
+        .add_component(Weapon::new(
+            enemy_fire_mode(shape),
+            ENEMY_BULLET,
+            ENEMY_FIRE_INTERVAL,
+        ))
+        .add_component(Health::new(enemy_health(shape)))
+        .add_component(Damage(ENEMY_CONTACT_DAMAGE))
+        .add_component(HitFlash::default())
+}
+
+/// Boxes take the most hits, circles go down in one.
+fn enemy_health(geometry: Geometry) -> i32 {
+    match geometry {
+        Geometry::Rectangle => 3,
+        Geometry::Circle => 1,
+        Geometry::Polygon { .. } => 2,
+    }
+}
+
+/// Boxes fire bursts, polygons a fan with a shot per corner and circles a
+/// single shot each time the player comes into range.
+fn enemy_fire_mode(geometry: Geometry) -> FireMode {
+    match geometry {
+        Geometry::Rectangle => FireMode::Burst {
+            shots: 3,
+            gap: Duration::from_millis(150),
+        },
+        Geometry::Circle => FireMode::Single,
+        Geometry::Polygon { sides } => FireMode::Spread {
+            shots: sides,
+            angle: ENEMY_SPREAD,
+        },
+    }
+}
+
+/// Half sized copy of a killed enemy's shape that flies off at `velocity` and
+/// fades away. Worth double the score of a whole enemy.
+pub fn create_small_enemy(
+    manager: &mut EntityManager,
+    shape: Shape,
+    transform: GameTransform,
+    velocity: Vec2,
+) -> &Entity {
+    let radius = shape.radius / 2f32;
+    let in_arena = arenas(manager)
+        .into_iter()
+        .find(|(_, bounds)| bounds.contains(transform.position));
+    let entity = manager
+        .add()
+        .add_component(tag::Enemy)
+        .add_component(tag::SmallEnemy)
+        .add_component(Shape { radius, ..shape })
+        .add_component(Collider {
+            center: transform.position,
+            radius,
+            rotation: transform.rotation,
+            layer: layer::ENEMY,
+            mask: layer::PLAYER | layer::BULLET | layer::OBSTACLE,
+            trigger: false,
+        })
+        .add_component(transform)
+        .add_component(Score(ENEMY_SCORE * 2))
+        .add_component(Speed { velocity })
+        .add_component(RigidBody::new(1f32))
+        .add_component(Lifespan {
+            time_left: SMALL_ENEMY_LIFESPAN,
+            total_time: SMALL_ENEMY_LIFESPAN,
+        })
+        .add_component(Solid::Dynamic)
+        .add_component(Health::new(1))
+        .add_component(Damage(ENEMY_CONTACT_DAMAGE))
+        .add_component(HitFlash::default());
+    match in_arena {
+        Some((arena, _)) => entity.add_component(Confined(arena)),
+        None => entity,
+    }
+}
+
+pub fn create_obstacle(manager: &mut EntityManager, position: Vec2) -> &Entity {
+    manager
+        .add_tag(tag::Obstacle)
+        .add_component(Shape {
+            geometry: Geometry::Rectangle,
+            radius: OBSTACLE_SIZE,
+        })
+        .add_component(GameTransform::new(position))
+        .add_component(Collider {
+            center: position,
+            radius: OBSTACLE_SIZE,
+            rotation: 0f32,
+            layer: layer::OBSTACLE,
+            mask: layer::PLAYER | layer::ENEMY,
+            trigger: false,
+        })
+        .add_component(Solid::Static)
+}
+
+/// Blocks spread around the arena, clear of the player's spawn in the middle.
+pub fn create_obstacles(manager: &mut EntityManager, bounds: Rect) {
+    let layout = [
+        (0.25, 0.25),
+        (0.75, 0.25),
+        (0.5, 0.15),
+        (0.25, 0.75),
+        (0.75, 0.75),
+        (0.5, 0.85),
+    ];
+    for (x, y) in layout {
+        let position = Vec2::new(bounds.x + bounds.w * x, bounds.y + bounds.h * y);
+        create_obstacle(manager, position);
+    }
 }
 
 pub fn create_enemy_spawner(manager: &mut EntityManager) -> &Entity {
@@ -126,22 +375,103 @@ pub fn create_enemy_spawner(manager: &mut EntityManager) -> &Entity {
         })
 }
 
-pub fn create_bullet_spawner(manager: &mut EntityManager) -> &Entity {
-    manager.add_tag(tag::Bullet).add_component(Spawner {
-        max: usize::MAX,
-        interval: BULLET_SPAWN_INTERVAL,
-        last_spawned_duration: Duration::from_secs(0),
-    })
+/// Column in the top left corner the score board and the special weapon are
+/// stacked in.
+pub fn create_hud_column(manager: &mut EntityManager) -> &Entity {
+    manager
+        .add_tag(tag::Ui)
+        .add_component(UiNode::new(Anchor::TopLeft).with_margin(Margin::all(12f32)))
+        .add_component(Stack {
+            direction: Direction::Vertical,
+            spacing: 8f32,
+        })
 }
 
-pub fn create_score_board(manager: &mut EntityManager) -> &Entity {
+pub fn create_score_board(manager: &mut EntityManager, column: EntityId) -> &Entity {
     manager
         .add_tag(tag::Ui)
         .add_component(Scoreboard { current_score: 0 })
+        .add_component(UiNode::new(Anchor::TopLeft).with_parent(column))
+}
+
+pub fn create_lives(manager: &mut EntityManager) -> &Entity {
+    manager
+        .add_tag(tag::Ui)
+        .add_component(Lives {
+            left: PLAYER_LIVES,
+            respawn: None,
+        })
+        .add_component(UiNode::new(Anchor::TopRight).with_margin(Margin::all(12f32)))
+}
+
+pub fn create_special_weapon(
+    manager: &mut EntityManager,
+    column: EntityId,
+    def: WeaponDef,
+) -> &Entity {
+    manager
+        .add_tag(tag::Ui)
+        .add_component(SpecialWeapon::new(def))
+        .add_component(UiNode::new(Anchor::TopLeft).with_parent(column))
 }
 
 pub fn create_display_text_ui(manager: &mut EntityManager) -> &Entity {
     manager
         .add_tag(tag::Ui)
         .add_component(DisplayText::default())
+        .add_component(UiNode::new(Anchor::Bottom).with_margin(Margin {
+            bottom: 32f32,
+            ..Default::default()
+        }))
+}
+
+#[cfg(test)]
+mod tests {
+    use common::game_transform::GameTransform;
+    use common::math::random::GameRng;
+    use common::math::Vec2;
+    use ecs::manager::EntityManager;
+    use ggez::graphics::Rect;
+
+    use super::constant::ENEMY_SIZE;
+    use super::{create_arena, create_enemy};
+
+    fn spawn_positions(bounds: Rect, count: usize) -> Vec<Vec2> {
+        let mut manager = EntityManager::new();
+        let mut rng = GameRng::new(4300);
+        let arena = create_arena(&mut manager, bounds).id;
+        for _ in 0..count {
+            create_enemy(&mut manager, &mut rng, arena, bounds);
+        }
+        manager.update();
+        manager
+            .query_entities_component::<GameTransform>()
+            .into_iter()
+            .map(|transform| transform.position)
+            .collect()
+    }
+
+    #[test]
+    fn test_enemy_spawns_inside_arena() {
+        let bounds = Rect::new(100f32, 50f32, 400f32, 300f32);
+        for position in spawn_positions(bounds, 20) {
+            assert!(
+                (bounds.left() + ENEMY_SIZE..=bounds.right() - ENEMY_SIZE).contains(&position.x)
+            );
+            assert!(
+                (bounds.top() + ENEMY_SIZE..=bounds.bottom() - ENEMY_SIZE).contains(&position.y)
+            );
+        }
+    }
+
+    #[test]
+    fn test_enemy_spawns_in_middle_of_tiny_arena() {
+        let bounds = Rect::new(10f32, 20f32, ENEMY_SIZE, ENEMY_SIZE * 4f32);
+        for position in spawn_positions(bounds, 5) {
+            assert_eq!(position.x, bounds.x + bounds.w / 2f32);
+            assert!(
+                (bounds.top() + ENEMY_SIZE..=bounds.bottom() - ENEMY_SIZE).contains(&position.y)
+            );
+        }
+    }
 }
@@ -3,3 +3,122 @@ use common::math::Vec2;
 pub struct Speed {
     pub velocity: Vec2,
 }
+
+/// Moves the entity's `Speed` in `integration_system`. Forces and impulses
+/// add up over a frame and are cleared once applied. Forces can't push the
+/// speed past `max_speed`, impulses can and bleed off through `drag`.
+#[derive(Clone, Copy, Debug)]
+pub struct RigidBody {
+    pub mass: f32,
+    pub acceleration: Vec2,
+    /// Share of the velocity lost per second.
+    pub drag: f32,
+    pub max_speed: f32,
+    /// Radians per second.
+    pub angular_velocity: f32,
+    force: Vec2,
+    impulse: Vec2,
+}
+
+impl RigidBody {
+    pub fn new(mass: f32) -> Self {
+        Self {
+            mass,
+            acceleration: Vec2::zero(),
+            drag: 0f32,
+            max_speed: f32::INFINITY,
+            angular_velocity: 0f32,
+            force: Vec2::zero(),
+            impulse: Vec2::zero(),
+        }
+    }
+
+    pub fn with_drag(mut self, drag: f32) -> Self {
+        self.drag = drag;
+        self
+    }
+
+    pub fn with_max_speed(mut self, max_speed: f32) -> Self {
+        self.max_speed = max_speed;
+        self
+    }
+
+    pub fn with_angular_velocity(mut self, angular_velocity: f32) -> Self {
+        self.angular_velocity = angular_velocity;
+        self
+    }
+
+    pub fn apply_force(&mut self, force: Vec2) {
+        self.force += force;
+    }
+
+    /// Changes the velocity at once on the next integration.
+    pub fn apply_impulse(&mut self, impulse: Vec2) {
+        self.impulse += impulse;
+    }
+
+    /// `velocity` after `dt` seconds.
+    pub fn integrate(&mut self, velocity: Vec2, dt: f32) -> Vec2 {
+        let acceleration = self.acceleration + self.force / self.mass;
+        let mut next = velocity + acceleration * dt;
+        let limit = self.max_speed.max(velocity.magnitude());
+        if next.magnitude() > limit {
+            next = next.normalized() * limit;
+        }
+        // Decays the same over a second whatever the frame rate.
+        next = next * (-self.drag * dt).exp() + self.impulse / self.mass;
+
+        self.force = Vec2::zero();
+        self.impulse = Vec2::zero();
+        next
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::math::Vec2;
+
+    use super::RigidBody;
+
+    fn assert_close(a: Vec2, b: Vec2) {
+        assert!(a.distance(b) < 1e-3, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn test_force_accelerates_by_mass() {
+        let mut body = RigidBody::new(2f32);
+        body.apply_force(Vec2::new(10f32, 0f32));
+        body.apply_force(Vec2::new(0f32, 4f32));
+        assert_close(body.integrate(Vec2::zero(), 0.5), Vec2::new(2.5, 1f32));
+        // The accumulator is cleared.
+        assert_close(
+            body.integrate(Vec2::new(1f32, 0f32), 0.5),
+            Vec2::new(1f32, 0f32),
+        );
+    }
+
+    #[test]
+    fn test_forces_capped_at_max_speed() {
+        let mut body = RigidBody::new(1f32).with_max_speed(10f32);
+        body.apply_force(Vec2::new(1000f32, 0f32));
+        assert_close(body.integrate(Vec2::zero(), 1f32), Vec2::new(10f32, 0f32));
+
+        // Faster bodies keep their speed but can't be pushed further.
+        body.apply_force(Vec2::new(1000f32, 0f32));
+        assert_close(
+            body.integrate(Vec2::new(30f32, 0f32), 1f32),
+            Vec2::new(30f32, 0f32),
+        );
+    }
+
+    #[test]
+    fn test_impulse_exceeds_max_speed_and_drag_slows_it() {
+        let mut body = RigidBody::new(2f32).with_max_speed(10f32).with_drag(1f32);
+        body.apply_impulse(Vec2::new(0f32, 100f32));
+        let velocity = body.integrate(Vec2::zero(), 0f32);
+        assert_close(velocity, Vec2::new(0f32, 50f32));
+
+        let slowed = body.integrate(velocity, 1f32);
+        assert_close(slowed, Vec2::new(0f32, 50f32 * (-1f32).exp()));
+    }
+}
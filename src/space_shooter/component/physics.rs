@@ -1,25 +1,106 @@
-use common::math::{collision::BoxCollision, Vec2};
+use common::game_transform::TryGet;
+use common::math::{collision::CollisionShape, Vec2};
+use ecs::entity::{Entity, EntityId};
+use ggez::graphics::Rect;
+use ggez::GameResult;
 
-#[derive(Clone, Copy)]
+use crate::space_shooter::component::shape::{Geometry, Shape};
+
+/// Bits for `Collider::layer` and `Collider::mask`.
+pub mod layer {
+    pub const PLAYER: u32 = 1 << 0;
+    pub const ENEMY: u32 = 1 << 1;
+    pub const BULLET: u32 = 1 << 2;
+    pub const OBSTACLE: u32 = 1 << 3;
+    pub const ENEMY_BULLET: u32 = 1 << 4;
+}
+
+/// `layer` is what the collider is, `mask` what it collides with. Triggers
+/// report collisions but are never pushed apart. `rotation` follows the
+/// entity's transform and turns box shapes.
+#[derive(Clone, Copy, Default)]
 pub struct Collider {
     pub center: Vec2,
     pub radius: f32,
+    pub rotation: f32,
+    pub layer: u32,
+    pub mask: u32,
+    pub trigger: bool,
 }
 
-impl From<Collider> for BoxCollision {
-    fn from(c: Collider) -> Self {
-        BoxCollision::new(
-            Vec2::new(c.center.x - c.radius, c.center.y - c.radius),
-            Vec2::new(c.radius * 2f32, c.radius * 2f32),
-        )
+impl Collider {
+    /// Pairs collide when either side's mask has the other's layer.
+    pub fn interacts(&self, other: &Collider) -> bool {
+        self.mask & other.layer != 0 || other.mask & self.layer != 0
     }
-}
 
-impl From<&Collider> for BoxCollision {
-    fn from(c: &Collider) -> Self {
-        BoxCollision::new(
-            Vec2::new(c.center.x - c.radius, c.center.y - c.radius),
-            Vec2::new(c.radius * 2f32, c.radius * 2f32),
+    /// Box around every shape the collider can take, at its rotation.
+    pub fn bounds(&self) -> Rect {
+        let (sin, cos) = self.rotation.sin_cos();
+        let half = self.radius * (sin.abs() + cos.abs());
+        Rect::new(
+            self.center.x - half,
+            self.center.y - half,
+            half * 2f32,
+            half * 2f32,
         )
     }
+
+    /// Narrow phase shape of a collider drawn as `geometry`.
+    pub fn shape(&self, geometry: Geometry) -> CollisionShape {
+        match geometry {
+            Geometry::Circle => CollisionShape::Circle {
+                center: self.center,
+                radius: self.radius,
+            },
+            Geometry::Rectangle if self.rotation == 0f32 => CollisionShape::Aabb {
+                center: self.center,
+                half_size: Vec2::new(self.radius, self.radius),
+            },
+            Geometry::Rectangle => CollisionShape::Obb {
+                center: self.center,
+                half_size: Vec2::new(self.radius, self.radius),
+                rotation: self.rotation,
+            },
+            Geometry::Polygon { sides } => CollisionShape::Polygon {
+                center: self.center,
+                radius: self.radius,
+                sides,
+                rotation: self.rotation,
+            },
+        }
+    }
+}
+
+/// Collision shape of an entity, picked by its `Shape` and a box when it has
+/// none.
+pub fn collision_shape(entity: &Entity) -> GameResult<CollisionShape> {
+    let collider = entity.try_get_component::<Collider>()?;
+    let geometry = entity
+        .get_component::<Shape>()
+        .map_or(Geometry::Rectangle, |shape| shape.geometry);
+    Ok(collider.shape(geometry))
+}
+
+/// Moves far enough in a frame to pass through other colliders, so its
+/// collisions are swept over the motion.
+#[derive(Clone, Copy)]
+pub struct FastMoving;
+
+/// Area of the world that confined entities are kept inside of.
+#[derive(Clone, Copy)]
+pub struct Arena {
+    pub bounds: Rect,
+}
+
+/// Keeps the entity inside the arena with this id.
+#[derive(Clone, Copy)]
+pub struct Confined(pub EntityId);
+
+/// Takes part in collision resolution. Static bodies never move, dynamic
+/// ones are pushed out and bounce when they have a `Speed`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Solid {
+    Static,
+    Dynamic,
 }
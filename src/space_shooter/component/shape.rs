@@ -1,9 +1,24 @@
+use crate::space_shooter::component::constant::CIRCLE_VERTICES;
+
 #[derive(Copy, Clone)]
 pub enum Geometry {
     Rectangle,
     Circle,
+    Polygon { sides: u32 },
+}
+
+impl Geometry {
+    /// Corners of the outline, circles count as `CIRCLE_VERTICES`.
+    pub fn vertex_count(self) -> u32 {
+        match self {
+            Geometry::Rectangle => 4,
+            Geometry::Circle => CIRCLE_VERTICES,
+            Geometry::Polygon { sides } => sides,
+        }
+    }
 }
 
+#[derive(Copy, Clone)]
 pub struct Shape {
     pub geometry: Geometry,
     pub radius: f32,
@@ -0,0 +1,440 @@
+use std::collections::HashMap;
+use std::f32::consts::TAU;
+use std::time::Duration;
+
+use common::math::Vec2;
+use ggez::{GameError, GameResult};
+use toml::Value;
+
+use crate::space_shooter::component::constant::{
+    BULLET_LIFESPAN, BULLET_SIZE, BULLET_SPEED, ENEMY_BULLET_SIZE, ENEMY_BULLET_SPEED,
+};
+use crate::space_shooter::component::physics::layer;
+use crate::space_shooter::component::shape::Geometry;
+
+const WEAPONS_PATH: &str = "/weapons.toml";
+
+/// What a projectile looks like, how it flies and what it does on a hit.
+#[derive(Clone, Copy)]
+pub struct ProjectileTemplate {
+    pub geometry: Geometry,
+    pub radius: f32,
+    pub speed: f32,
+    pub lifespan: Duration,
+    pub damage: i32,
+    /// Targets the projectile passes through before it is spent.
+    pub pierce: u32,
+    /// Radians per second the projectile turns towards its nearest target,
+    /// zero flies straight.
+    pub homing: f32,
+    pub layer: u32,
+    pub mask: u32,
+}
+
+pub const PLAYER_BULLET: ProjectileTemplate = ProjectileTemplate {
+    geometry: Geometry::Circle,
+    radius: BULLET_SIZE,
+    speed: BULLET_SPEED,
+    lifespan: BULLET_LIFESPAN,
+    damage: 1,
+    pierce: 0,
+    homing: 0f32,
+    layer: layer::BULLET,
+    mask: layer::ENEMY,
+};
+
+pub const ENEMY_BULLET: ProjectileTemplate = ProjectileTemplate {
+    geometry: Geometry::Polygon { sides: 3 },
+    radius: ENEMY_BULLET_SIZE,
+    speed: ENEMY_BULLET_SPEED,
+    lifespan: BULLET_LIFESPAN,
+    damage: 1,
+    pierce: 0,
+    homing: 0f32,
+    layer: layer::ENEMY_BULLET,
+    mask: layer::PLAYER,
+};
+
+/// Carried by a fired projectile, next to the `Damage` it deals.
+#[derive(Clone, Copy)]
+pub struct Projectile {
+    pub pierce: u32,
+}
+
+/// Turns a projectile's velocity towards the nearest collider its mask hits.
+#[derive(Clone, Copy)]
+pub struct Homing {
+    pub turn_rate: f32,
+}
+
+/// Every mode but `Single` keeps firing once per interval while the trigger
+/// is held.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FireMode {
+    /// One shot per trigger pull.
+    Single,
+    Automatic,
+    /// `shots` in a row, `gap` apart.
+    Burst {
+        shots: u32,
+        gap: Duration,
+    },
+    /// `shots` at once fanned over `angle` radians.
+    Spread {
+        shots: u32,
+        angle: f32,
+    },
+}
+
+/// Fires `projectile`s along `aim` while `trigger` is set, at most once per
+/// `interval`. Whoever holds the weapon sets the trigger and aim.
+pub struct Weapon {
+    pub mode: FireMode,
+    pub projectile: ProjectileTemplate,
+    pub interval: Duration,
+    pub trigger: bool,
+    pub aim: Vec2,
+    was_triggered: bool,
+    last_fired: Option<Duration>,
+    burst_left: u32,
+}
+
+impl Weapon {
+    pub fn new(mode: FireMode, projectile: ProjectileTemplate, interval: Duration) -> Self {
+        Self {
+            mode,
+            projectile,
+            interval,
+            trigger: false,
+            aim: Vec2::zero(),
+            was_triggered: false,
+            last_fired: None,
+            burst_left: 0,
+        }
+    }
+
+    fn ready(&self, now: Duration, wait: Duration) -> bool {
+        self.last_fired.is_none_or(|fired| now >= fired + wait)
+    }
+
+    /// Directions of the projectiles fired at `now`, in radians. Empty when
+    /// the weapon does not fire.
+    pub fn fire(&mut self, now: Duration) -> Vec<f32> {
+        let pulled = self.trigger && !self.was_triggered;
+        self.was_triggered = self.trigger;
+        let aim = self.aim.angle();
+
+        if self.burst_left > 0 {
+            if let FireMode::Burst { gap, .. } = self.mode {
+                if self.ready(now, gap) {
+                    self.burst_left -= 1;
+                    self.last_fired = Some(now);
+                    return vec![aim];
+                }
+            }
+            return vec![];
+        }
+
+        let fires = match self.mode {
+            FireMode::Single => pulled,
+            _ => self.trigger,
+        };
+        if !fires || self.aim == Vec2::zero() || !self.ready(now, self.interval) {
+            return vec![];
+        }
+        self.last_fired = Some(now);
+        match self.mode {
+            FireMode::Single | FireMode::Automatic => vec![aim],
+            FireMode::Burst { shots, .. } => {
+                self.burst_left = shots.saturating_sub(1);
+                vec![aim]
+            }
+            FireMode::Spread { shots, angle } => fan(aim, shots, angle),
+        }
+    }
+}
+
+/// `count` directions spread evenly over `spread` radians around `aim`, a
+/// full turn spreads them around a ring.
+pub fn fan(aim: f32, count: u32, spread: f32) -> Vec<f32> {
+    let count = count as usize;
+    // A full ring would put the last direction on top of the first.
+    let gaps = if spread >= TAU {
+        count
+    } else {
+        count.saturating_sub(1).max(1)
+    };
+    let step = spread / gaps as f32;
+    let first = if spread >= TAU || count == 1 {
+        aim
+    } else {
+        aim - spread / 2f32
+    };
+    (0..count).map(|i| first + step * i as f32).collect()
+}
+
+/// What a weapon fires: `bullets` fanned evenly over `spread` radians around
+/// the aim, a full turn fires a ring.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WeaponDef {
+    pub bullets: u32,
+    pub spread: f32,
+    pub speed: f32,
+    pub lifespan: Duration,
+    pub cooldown: Duration,
+    pub damage: i32,
+    pub pierce: u32,
+    pub homing: f32,
+}
+
+impl Default for WeaponDef {
+    fn default() -> Self {
+        Self {
+            bullets: 16,
+            spread: TAU,
+            speed: 500f32,
+            lifespan: Duration::from_secs(1),
+            cooldown: Duration::from_secs(5),
+            damage: PLAYER_BULLET.damage,
+            pierce: 0,
+            homing: 0f32,
+        }
+    }
+}
+
+impl WeaponDef {
+    /// Directions of the bullets fired towards `aim`, in radians.
+    pub fn angles(&self, aim: f32) -> Vec<f32> {
+        fan(aim, self.bullets, self.spread)
+    }
+
+    /// Player bullet flying as this weapon defines.
+    pub fn projectile(&self) -> ProjectileTemplate {
+        ProjectileTemplate {
+            speed: self.speed,
+            lifespan: self.lifespan,
+            damage: self.damage,
+            pierce: self.pierce,
+            homing: self.homing,
+            ..PLAYER_BULLET
+        }
+    }
+
+    fn from_table(name: &str, table: &Value) -> GameResult<Self> {
+        let number = |key: &str| -> GameResult<f64> {
+            match table.get(key) {
+                Some(Value::Integer(n)) => Ok(*n as f64),
+                Some(Value::Float(n)) => Ok(*n),
+                _ => Err(GameError::CustomError(format!(
+                    "Weapon {} needs a number for {}",
+                    name, key
+                ))),
+            }
+        };
+        let optional = |key: &str, default: f64| -> GameResult<f64> {
+            match table.get(key) {
+                Some(_) => number(key),
+                None => Ok(default),
+            }
+        };
+        let seconds = |key: &str| -> GameResult<Duration> {
+            Duration::try_from_secs_f64(number(key)?).map_err(|_| {
+                GameError::CustomError(format!("Weapon {} has a negative {}", name, key))
+            })
+        };
+        Ok(Self {
+            bullets: number("bullets")?.max(1f64) as u32,
+            spread: (number("spread")? as f32).to_radians().clamp(0f32, TAU),
+            speed: number("speed")? as f32,
+            lifespan: seconds("lifespan")?,
+            cooldown: seconds("cooldown")?,
+            damage: optional("damage", PLAYER_BULLET.damage as f64)? as i32,
+            pierce: optional("pierce", 0f64)?.max(0f64) as u32,
+            homing: (optional("homing", 0f64)? as f32).to_radians().max(0f32),
+        })
+    }
+
+    /// The weapon picked by `special` among the `[weapons.<name>]` tables.
+    pub fn load_config(config: &str) -> GameResult<Self> {
+        let table: HashMap<String, Value> = toml::from_str(config)
+            .map_err(|e| GameError::CustomError(format!("Invalid weapons config: {}", e)))?;
+        let name = table
+            .get("special")
+            .and_then(Value::as_str)
+            .ok_or_else(|| GameError::CustomError("No special weapon picked".to_string()))?;
+        let weapon = table
+            .get("weapons")
+            .and_then(|weapons| weapons.get(name))
+            .ok_or_else(|| GameError::CustomError(format!("Unknown weapon {}", name)))?;
+        Self::from_table(name, weapon)
+    }
+}
+
+/// Built-in special weapon overridden by `resources/weapons.toml` when `read`
+/// finds it.
+pub fn load_special_weapon<F>(read: F) -> GameResult<WeaponDef>
+where
+    F: FnOnce(&str) -> GameResult<Option<String>>,
+{
+    match read(WEAPONS_PATH)? {
+        Some(config) => WeaponDef::load_config(&config),
+        None => Ok(WeaponDef::default()),
+    }
+}
+
+pub struct SpecialWeapon {
+    pub def: WeaponDef,
+    pub last_fired: Option<Duration>,
+}
+
+impl SpecialWeapon {
+    pub fn new(def: WeaponDef) -> Self {
+        Self {
+            def,
+            last_fired: None,
+        }
+    }
+
+    /// Time until the weapon can fire again at `now`.
+    pub fn cooldown_left(&self, now: Duration) -> Duration {
+        self.last_fired.map_or(Duration::ZERO, |fired| {
+            (fired + self.def.cooldown).saturating_sub(now)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::{FRAC_PI_2, TAU};
+    use std::time::Duration;
+
+    use common::math::Vec2;
+
+    use super::{FireMode, SpecialWeapon, Weapon, WeaponDef, PLAYER_BULLET};
+
+    const INTERVAL: Duration = Duration::from_millis(300);
+
+    fn weapon(mode: FireMode) -> Weapon {
+        let mut weapon = Weapon::new(mode, PLAYER_BULLET, INTERVAL);
+        weapon.aim = Vec2::new(1f32, 0f32);
+        weapon
+    }
+
+    /// Shots fired on each frame of `frames` 100 ms apart, with the trigger
+    /// held on the frames in `held`.
+    fn shots(weapon: &mut Weapon, frames: u64, held: impl Fn(u64) -> bool) -> Vec<usize> {
+        (0..frames)
+            .map(|frame| {
+                weapon.trigger = held(frame);
+                weapon.fire(Duration::from_millis(frame * 100)).len()
+            })
+            .collect()
+    }
+
+    const CONFIG: &str = r#"
+        special = "fan"
+
+        [weapons.fan]
+        bullets = 3
+        spread = 90
+        speed = 400
+        lifespan = 0.5
+        cooldown = 2
+        pierce = 2
+    "#;
+
+    #[test]
+    fn test_load_config() {
+        let def = WeaponDef::load_config(CONFIG).unwrap();
+        assert_eq!(def.bullets, 3);
+        assert!((def.spread - FRAC_PI_2).abs() < 1e-6);
+        assert_eq!(def.speed, 400f32);
+        assert_eq!(def.lifespan, Duration::from_millis(500));
+        assert_eq!(def.cooldown, Duration::from_secs(2));
+        assert_eq!(def.pierce, 2);
+        assert_eq!(def.damage, 1);
+        assert_eq!(def.homing, 0f32);
+    }
+
+    #[test]
+    fn test_load_config_errors() {
+        assert!(WeaponDef::load_config(&CONFIG.replace("\"fan\"", "\"beam\"")).is_err());
+        assert!(WeaponDef::load_config(&CONFIG.replace("speed = 400", "")).is_err());
+        assert!(WeaponDef::load_config(&CONFIG.replace("= 2", "= -2")).is_err());
+    }
+
+    #[test]
+    fn test_angles() {
+        let fan = WeaponDef::load_config(CONFIG).unwrap();
+        let angles = fan.angles(1f32);
+        assert_eq!(angles.len(), 3);
+        assert!((angles[0] - (1f32 - FRAC_PI_2 / 2f32)).abs() < 1e-6);
+        assert!((angles[1] - 1f32).abs() < 1e-6);
+        assert!((angles[2] - (1f32 + FRAC_PI_2 / 2f32)).abs() < 1e-6);
+
+        let ring = WeaponDef::default().angles(0f32);
+        assert!((ring[1] - TAU / 16f32).abs() < 1e-6);
+        assert!((ring[15] - TAU * 15f32 / 16f32).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_cooldown_left() {
+        let mut weapon = SpecialWeapon::new(WeaponDef::default());
+        assert_eq!(weapon.cooldown_left(Duration::ZERO), Duration::ZERO);
+        weapon.last_fired = Some(Duration::from_secs(1));
+        assert_eq!(
+            weapon.cooldown_left(Duration::from_secs(2)),
+            Duration::from_secs(4)
+        );
+        assert_eq!(weapon.cooldown_left(Duration::from_secs(9)), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_single_fires_once_per_pull() {
+        // Pulled again before the interval is over.
+        let mut single = weapon(FireMode::Single);
+        assert_eq!(
+            shots(&mut single, 8, |frame| frame != 1),
+            [1, 0, 0, 0, 0, 0, 0, 0]
+        );
+        let mut single = weapon(FireMode::Single);
+        assert_eq!(
+            shots(&mut single, 8, |frame| frame % 4 != 3),
+            [1, 0, 0, 0, 1, 0, 0, 0]
+        );
+    }
+
+    #[test]
+    fn test_automatic_fires_every_interval() {
+        let mut automatic = weapon(FireMode::Automatic);
+        assert_eq!(
+            shots(&mut automatic, 8, |frame| frame < 7),
+            [1, 0, 0, 1, 0, 0, 1, 0]
+        );
+    }
+
+    #[test]
+    fn test_burst_and_spread() {
+        let mut burst = weapon(FireMode::Burst {
+            shots: 3,
+            gap: Duration::from_millis(200),
+        });
+        assert_eq!(
+            shots(&mut burst, 8, |frame| frame == 0),
+            [1, 0, 1, 0, 1, 0, 0, 0]
+        );
+
+        let mut spread = weapon(FireMode::Spread {
+            shots: 5,
+            angle: 1f32,
+        });
+        assert_eq!(shots(&mut spread, 2, |_| true), [5, 0]);
+    }
+
+    #[test]
+    fn test_no_shot_without_aim() {
+        let mut automatic = weapon(FireMode::Automatic);
+        automatic.aim = Vec2::zero();
+        assert_eq!(shots(&mut automatic, 3, |_| true), [0, 0, 0]);
+    }
+}
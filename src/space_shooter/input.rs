@@ -0,0 +1,188 @@
+use std::str::FromStr;
+
+use common::camera::Camera;
+use common::input::{ActionMap, ActionState, Binding};
+use common::math::Vec2;
+use ggez::event::{Button, KeyCode, MouseButton};
+use ggez::{GameError, GameResult};
+
+const BINDINGS_PATH: &str = "/bindings.toml";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Boost,
+    Fire,
+    Special,
+    Pause,
+}
+
+impl FromStr for Action {
+    type Err = GameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "MoveUp" => Ok(Action::MoveUp),
+            "MoveDown" => Ok(Action::MoveDown),
+            "MoveLeft" => Ok(Action::MoveLeft),
+            "MoveRight" => Ok(Action::MoveRight),
+            "Boost" => Ok(Action::Boost),
+            "Fire" => Ok(Action::Fire),
+            "Special" => Ok(Action::Special),
+            "Pause" => Ok(Action::Pause),
+            _ => Err(GameError::CustomError(format!("Unknown action {}", s))),
+        }
+    }
+}
+
+pub fn default_bindings() -> ActionMap<Action> {
+    let mut map = ActionMap::new();
+    map.bind(Action::MoveUp, Binding::Key(KeyCode::W))
+        .bind(Action::MoveDown, Binding::Key(KeyCode::S))
+        .bind(Action::MoveLeft, Binding::Key(KeyCode::A))
+        .bind(Action::MoveRight, Binding::Key(KeyCode::D))
+        .bind(Action::Boost, Binding::Key(KeyCode::LShift))
+        .bind(Action::Boost, Binding::Gamepad(Button::LeftTrigger2))
+        .bind(Action::Fire, Binding::Mouse(MouseButton::Left))
+        .bind(Action::Fire, Binding::Gamepad(Button::RightTrigger2))
+        .bind(Action::Special, Binding::Mouse(MouseButton::Right))
+        .bind(Action::Special, Binding::Gamepad(Button::RightTrigger))
+        .bind(Action::Pause, Binding::Key(KeyCode::Escape))
+        .bind(Action::Pause, Binding::Gamepad(Button::Start));
+    map
+}
+
+/// Default bindings overridden by `resources/bindings.toml` when `read`
+/// finds it.
+pub fn load_bindings<F>(read: F) -> GameResult<ActionMap<Action>>
+where
+    F: FnOnce(&str) -> GameResult<Option<String>>,
+{
+    let mut map = default_bindings();
+    if let Some(config) = read(BINDINGS_PATH)? {
+        map.load_config(&config)?;
+    }
+    Ok(map)
+}
+
+/// Direction the player wants to move in. The left stick keeps its analog
+/// magnitude and wins over the digital actions when pushed.
+pub fn movement_direction(input: &ActionState<Action>) -> Vec2 {
+    let stick = input.left_stick();
+    if stick != Vec2::zero() {
+        return stick;
+    }
+
+    let mut dir = Vec2::zero();
+    if input.pressed(Action::MoveUp) {
+        dir.y += -1f32;
+    }
+    if input.pressed(Action::MoveDown) {
+        dir.y += 1f32;
+    }
+    if input.pressed(Action::MoveLeft) {
+        dir.x += -1f32;
+    }
+    if input.pressed(Action::MoveRight) {
+        dir.x += 1f32;
+    }
+    dir.normalized()
+}
+
+/// Point the player aims at. The right stick places it `reach` away from
+/// `origin`, otherwise the mouse cursor is seen through the camera.
+pub fn aim_target(input: &ActionState<Action>, camera: &Camera, origin: Vec2, reach: f32) -> Vec2 {
+    let stick = input.right_stick();
+    if stick != Vec2::zero() {
+        origin + stick.normalized() * reach
+    } else {
+        camera.screen_to_world(input.mouse_position())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::camera::Camera;
+    use common::input::{ActionState, GamepadState, InputState};
+    use common::math::Vec2;
+    use ggez::event::{Axis, KeyCode};
+
+    use super::{aim_target, default_bindings, movement_direction, Action};
+
+    fn action_state(input: InputState) -> ActionState<Action> {
+        let mut state = ActionState::new(default_bindings());
+        state.update(input);
+        state
+    }
+
+    #[test]
+    fn test_movement_from_keys() {
+        let state = action_state(InputState {
+            keys: [KeyCode::W, KeyCode::D].into_iter().collect(),
+            ..Default::default()
+        });
+
+        let dir = movement_direction(&state);
+        assert!((dir.magnitude() - 1f32).abs() < 1e-5);
+        assert!(dir.x > 0f32 && dir.y < 0f32);
+    }
+
+    #[test]
+    fn test_movement_from_stick() {
+        let mut gamepad = GamepadState::default();
+        gamepad.axis_changed(Axis::LeftStickX, 1f32);
+        let state = action_state(InputState {
+            keys: [KeyCode::W].into_iter().collect(),
+            gamepad,
+            ..Default::default()
+        });
+
+        assert_eq!(movement_direction(&state), Vec2::new(1f32, 0f32));
+    }
+
+    #[test]
+    fn test_movement_ignores_stick_drift() {
+        let mut gamepad = GamepadState::default();
+        gamepad.axis_changed(Axis::LeftStickX, 0.05);
+        let state = action_state(InputState {
+            gamepad,
+            ..Default::default()
+        });
+
+        assert_eq!(movement_direction(&state), Vec2::zero());
+    }
+
+    #[test]
+    fn test_aim_prefers_right_stick() {
+        let mut gamepad = GamepadState::default();
+        gamepad.axis_changed(Axis::RightStickY, 1f32);
+        let state = action_state(InputState {
+            mouse_position: Vec2::new(500f32, 500f32),
+            gamepad,
+            ..Default::default()
+        });
+
+        let camera = Camera::new(Vec2::new(800f32, 600f32));
+        let target = aim_target(&state, &camera, Vec2::new(100f32, 100f32), 50f32);
+        assert_eq!(target, Vec2::new(100f32, 50f32));
+    }
+
+    #[test]
+    fn test_aim_falls_back_to_mouse() {
+        let state = action_state(InputState {
+            mouse_position: Vec2::new(500f32, 500f32),
+            ..Default::default()
+        });
+
+        let mut camera = Camera::new(Vec2::new(800f32, 600f32));
+        let target = aim_target(&state, &camera, Vec2::new(100f32, 100f32), 50f32);
+        assert_eq!(target, Vec2::new(500f32, 500f32));
+
+        camera.position += Vec2::new(1000f32, 0f32);
+        let target = aim_target(&state, &camera, Vec2::new(100f32, 100f32), 50f32);
+        assert_eq!(target, Vec2::new(1500f32, 500f32));
+    }
+}
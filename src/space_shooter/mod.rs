@@ -1,92 +1,390 @@
-use crate::ui::render_fps_system;
+use crate::scene::{Scene, Transition};
+use crate::space_shooter::component::constant::{ARENA_HEIGHT, ARENA_WIDTH};
+use crate::space_shooter::component::game::{Lives, Scoreboard};
+use crate::space_shooter::component::weapon::{load_special_weapon, WeaponDef};
+use crate::space_shooter::input::Action;
+use crate::space_shooter::replay::InputSource;
+use crate::space_shooter::scene::{GameOverScene, PauseScene};
+use crate::ui::{render_fps_system, ui_layout_system};
+use crate::{LOGICAL_HEIGHT, LOGICAL_WIDTH};
+use common::camera::Camera;
 use common::event::EventSystem;
+use common::input::{ActionState, GamepadState, InputState, DEFAULT_DEAD_ZONE};
+use common::math::random::GameRng;
+use common::math::Vec2;
+use common::physics::ContactTracker;
+use common::replay::{Replay, ReplayPlayer};
+use common::spatial::SpatialGrid;
+use common::time::GameTime;
+use ecs::entity::EntityId;
 use ecs::manager::EntityManager;
-use ggez::event::EventHandler;
-use ggez::graphics::Color;
-use ggez::{Context, GameError};
+use ggez::event::{Axis, Button};
+use ggez::graphics::Rect;
+use ggez::{Context, GameResult};
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::PathBuf;
+use std::time::Duration;
 
 mod component;
+mod input;
+mod replay;
+mod scene;
 mod system;
 
 pub mod tag {
     pub struct Player;
     pub struct Enemy;
+    /// Piece of a killed enemy, does not split again.
+    pub struct SmallEnemy;
     pub struct Bullet;
     pub struct Ui;
     pub struct Spawner;
+    pub struct Obstacle;
+}
+
+/// Settings a new run is started with, kept around so "Main Menu" can start
+/// the next run the same way.
+#[derive(Clone, Debug)]
+pub struct GameOptions {
+    pub seed: Option<u64>,
+    pub record: Option<PathBuf>,
+    pub dead_zone: f32,
+}
+
+impl Default for GameOptions {
+    fn default() -> Self {
+        Self {
+            seed: None,
+            record: None,
+            dead_zone: DEFAULT_DEAD_ZONE,
+        }
+    }
+}
+
+impl GameOptions {
+    pub fn new_game(&self) -> SpaceGame {
+        let seed = self.seed.unwrap_or_else(rand::random);
+        eprintln!("Running with seed {}", seed);
+
+        let mut game = SpaceGame::new(seed);
+        game.options = self.clone();
+        game.input.set_dead_zone(self.dead_zone);
+        match &self.record {
+            Some(path) => game.record_to(path.clone()),
+            None => game,
+        }
+    }
+}
+
+/// Contents of a file in the ggez filesystem, `None` when it does not exist.
+fn read_resource(ctx: &mut Context, path: &str) -> GameResult<Option<String>> {
+    if !ggez::filesystem::exists(ctx, path) {
+        return Ok(None);
+    }
+    let mut contents = String::new();
+    ggez::filesystem::open(ctx, path)?.read_to_string(&mut contents)?;
+    Ok(Some(contents))
 }
 
 #[derive(Default)]
 pub struct SpaceGame {
     entity_manager: EntityManager,
     event_system: EventSystem,
+    rng: GameRng,
+    time: GameTime,
+    input: ActionState<Action>,
+    input_source: InputSource,
+    gamepad: GamepadState,
+    camera: Camera,
+    broad_phase: SpatialGrid<EntityId>,
+    contacts: ContactTracker,
+    special_weapon: WeaponDef,
+    options: GameOptions,
     setup: bool,
 }
 
 impl SpaceGame {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: GameRng::new(seed),
+            input: ActionState::new(input::default_bindings()),
+            camera: Camera::new(Vec2::new(LOGICAL_WIDTH, LOGICAL_HEIGHT)),
+            ..Default::default()
+        }
+    }
+
+    pub fn record_to(mut self, path: PathBuf) -> Self {
+        self.input_source = InputSource::Record {
+            path,
+            replay: Replay::new(self.rng.seed()),
+        };
+        self
+    }
+
+    pub fn from_replay(replay: Replay) -> Self {
+        let mut game = Self::new(replay.seed);
+        game.input_source = InputSource::Playback(ReplayPlayer::new(replay));
+        game
+    }
+
+    /// Loads the bindings and the special weapon. `read` gives the contents
+    /// of a data file, or `None` when it does not exist, so headless playback
+    /// goes through the same loaders as the game.
+    pub fn load_data<F>(&mut self, mut read: F) -> GameResult<()>
+    where
+        F: FnMut(&str) -> GameResult<Option<String>>,
+    {
+        self.input.set_map(input::load_bindings(&mut read)?);
+        self.special_weapon = load_special_weapon(&mut read)?;
+        Ok(())
+    }
+
     fn setup(&mut self) {
         self.setup = true;
-        component::create_player(&mut self.entity_manager);
-        component::create_enemy(&mut self.entity_manager);
+        let bounds = Rect::new(0f32, 0f32, ARENA_WIDTH, ARENA_HEIGHT);
+        let arena = component::create_arena(&mut self.entity_manager, bounds).id;
+        let center = Vec2::new(bounds.x + bounds.w / 2f32, bounds.y + bounds.h / 2f32);
+        component::create_player(&mut self.entity_manager, arena, center);
+        self.camera.position = center;
+        component::create_enemy(&mut self.entity_manager, &mut self.rng, arena, bounds);
+        component::create_obstacles(&mut self.entity_manager, bounds);
         component::create_enemy_spawner(&mut self.entity_manager);
-        component::create_bullet_spawner(&mut self.entity_manager);
-        component::create_score_board(&mut self.entity_manager);
+        let column = component::create_hud_column(&mut self.entity_manager).id;
+        component::create_score_board(&mut self.entity_manager, column);
+        component::create_lives(&mut self.entity_manager);
+        component::create_special_weapon(
+            &mut self.entity_manager,
+            column,
+            self.special_weapon.clone(),
+        );
         component::create_display_text_ui(&mut self.entity_manager);
     }
-}
 
-impl EventHandler for SpaceGame {
-    fn update(&mut self, ctx: &mut Context) -> Result<(), GameError> {
+    /// Advances the simulation by one frame without touching the ggez context.
+    pub fn step(&mut self, delta: Duration, input: InputState) -> GameResult<()> {
         if !self.setup {
             self.setup();
         }
         self.entity_manager.update();
+        self.time.advance(delta);
+        self.input.update(input);
 
         system::ui::lifetime_debug_text_system(
             &mut self.event_system,
             &mut self.entity_manager,
-            ctx,
+            &self.time,
         );
 
-        system::game::lifespan_system(&mut self.entity_manager, ctx)?;
-        system::game::enemy_spawner(&mut self.entity_manager, ctx)?;
+        system::game::lifespan_system(&mut self.entity_manager, &self.time)?;
+        system::combat::health_timer_system(&mut self.entity_manager, &self.time)?;
+        system::game::enemy_spawner(&mut self.entity_manager, &mut self.rng, &self.time)?;
 
         system::movement::player_speed_boost_system(
             &mut self.entity_manager,
-            ctx,
+            &self.input,
+            &self.time,
             &mut self.event_system,
         )?;
-        system::movement::player_movement_system(&mut self.entity_manager, ctx)?;
-        system::movement::enemy_movement_system(
+        system::movement::player_control_system(&mut self.entity_manager, &self.input)?;
+        system::movement::enemy_bounce_system(&mut self.entity_manager, &mut self.event_system)?;
+        system::movement::homing_system(&mut self.entity_manager, &self.time)?;
+        system::movement::integration_system(&mut self.entity_manager, &self.time)?;
+        system::movement::collider_follow_transform_system(&mut self.entity_manager)?;
+        system::collision::broad_phase_system(&mut self.entity_manager, &mut self.broad_phase)?;
+        system::collision::collision_event_system(
             &mut self.entity_manager,
+            &self.broad_phase,
+            &mut self.contacts,
+            &self.time,
             &mut self.event_system,
-            ctx,
         )?;
-        system::movement::collider_follow_transform_system(&mut self.entity_manager)?;
-        system::movement::bullet_movement_system(&mut self.entity_manager, ctx)?;
 
-        system::game::shoot_system(&mut self.entity_manager, ctx)?;
-        system::game::kill_enemy_system(&mut self.entity_manager, &mut self.event_system)?;
+        system::camera::camera_follow_system(
+            &mut self.entity_manager,
+            &mut self.camera,
+            &self.time,
+        )?;
+
+        system::game::player_trigger_system(&mut self.entity_manager, &self.input, &self.camera)?;
+        system::game::enemy_trigger_system(&mut self.entity_manager)?;
+        system::game::shoot_system(&mut self.entity_manager, &self.time)?;
+        system::game::special_weapon_system(
+            &mut self.entity_manager,
+            &self.input,
+            &self.camera,
+            &self.time,
+        )?;
+        system::combat::damage_system(&mut self.entity_manager, &self.event_system)?;
+        system::combat::enemy_death_system(&mut self.entity_manager, &mut self.event_system)?;
+        system::game::split_enemy_system(&mut self.entity_manager, &self.event_system)?;
 
-        system::collision::windows_bound_collision_system(
+        system::collision::arena_bound_collision_system(
             &mut self.entity_manager,
             &mut self.event_system,
         )?;
-        system::collision::player_collision_system(&mut self.entity_manager)?;
+        system::combat::player_death_system(
+            &mut self.entity_manager,
+            &self.time,
+            &mut self.event_system,
+        )?;
+        system::combat::respawn_system(&mut self.entity_manager, &self.time)?;
+        system::collision::collision_resolution_system(
+            &mut self.entity_manager,
+            &self.event_system,
+        )?;
+        system::camera::camera_shake_system(&mut self.event_system, &mut self.camera, &self.time);
         Ok(())
     }
 
-    fn draw(&mut self, ctx: &mut Context) -> Result<(), GameError> {
-        ggez::graphics::clear(ctx, Color::WHITE);
+    fn score(&self) -> i32 {
+        self.entity_manager
+            .query_entities_component::<Scoreboard>()
+            .first()
+            .map_or(0, |board| board.current_score)
+    }
+
+    fn lives(&self) -> u32 {
+        self.entity_manager
+            .query_entities_component::<Lives>()
+            .first()
+            .map_or(0, |lives| lives.left)
+    }
+
+    /// The run is over once the last life is lost.
+    fn game_over(&self) -> bool {
+        self.setup && self.lives() == 0
+    }
+
+    /// Numbers a replay is checked against when it is played back.
+    pub fn outcome(&mut self) -> BTreeMap<String, i64> {
+        let score = self.score();
+        let enemies = self
+            .entity_manager
+            .get_entities_with_tag::<tag::Enemy>()
+            .len();
+        let bullets = self
+            .entity_manager
+            .get_entities_with_tag::<tag::Bullet>()
+            .len();
+        // The HUD is left out so ui changes do not move a replay's outcome.
+        let entities = self
+            .entity_manager
+            .get_all()
+            .iter()
+            .filter(|entity| !entity.has_component::<tag::Ui>())
+            .count();
+
+        BTreeMap::from([
+            ("score".to_string(), score as i64),
+            ("enemies".to_string(), enemies as i64),
+            ("bullets".to_string(), bullets as i64),
+            ("entities".to_string(), entities as i64),
+            ("lives".to_string(), self.lives() as i64),
+        ])
+    }
+
+    fn finish_playback(&mut self) {
+        let outcome = self.outcome();
+        if let InputSource::Playback(player) = &self.input_source {
+            let expected = &player.replay().outcome;
+            if !expected.is_empty() && *expected != outcome {
+                eprintln!(
+                    "Replay diverged, expected {:?} but got {:?}",
+                    expected, outcome
+                );
+            }
+        }
+    }
+}
+
+impl Scene for SpaceGame {
+    fn update(&mut self, ctx: &mut Context) -> GameResult<Transition> {
+        let frame = match &mut self.input_source {
+            InputSource::Live => Some((
+                ggez::timer::delta(ctx),
+                InputState::capture(ctx, &self.gamepad),
+            )),
+            InputSource::Record { replay, .. } => {
+                let delta = ggez::timer::delta(ctx);
+                let input = InputState::capture(ctx, &self.gamepad);
+                replay.push(delta, input.clone());
+                Some((delta, input))
+            }
+            InputSource::Playback(player) => {
+                player.next_frame().map(|f| (f.delta, f.input.clone()))
+            }
+        };
+
+        let finished = match frame {
+            Some((delta, input)) => {
+                self.step(delta, input)?;
+                self.game_over()
+            }
+            None => true,
+        };
+        if finished {
+            self.finish_playback();
+            let game_over = GameOverScene::new(self.score(), self.options.clone());
+            return Ok(Transition::Reset(Box::new(game_over)));
+        }
+
+        // Recorded pause presses are ignored so playback runs uninterrupted.
+        let playback = matches!(self.input_source, InputSource::Playback(_));
+        if !playback && self.input.just_pressed(Action::Pause) {
+            let pause =
+                PauseScene::new(self.score(), self.input.map().clone(), self.options.clone());
+            return Ok(Transition::Push(Box::new(pause)));
+        }
+        Ok(Transition::None)
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        system::render::render_arena_system(&mut self.entity_manager, &self.camera, ctx)?;
+        system::render::render_shape_system(&mut self.entity_manager, &self.camera, ctx)?;
+        system::game::aim_system(
+            &mut self.entity_manager,
+            &self.broad_phase,
+            &self.input,
+            &self.camera,
+            ctx,
+        )?;
 
-        system::render::render_shape_system(&mut self.entity_manager, ctx)?;
         render_fps_system(ctx)?;
-        system::game::aim_system(&mut self.entity_manager, ctx)?;
+        system::ui::cache_debug_text_system(&mut self.entity_manager);
+        let time = &self.time;
+        ui_layout_system(&mut self.entity_manager, ctx, |entity, ctx| {
+            system::render::measure_hud(entity, time, ctx)
+        })?;
         system::render::render_scoreboard_system(&self.entity_manager, ctx)?;
-        system::ui::display_debug_text_system(&mut self.entity_manager, ctx)?;
+        system::render::render_lives_system(&self.entity_manager, ctx)?;
+        system::render::render_special_weapon_system(&self.entity_manager, &self.time, ctx)?;
+        system::ui::display_debug_text_system(&self.entity_manager, ctx)
+    }
 
-        ggez::graphics::present(ctx)?;
-        ggez::timer::yield_now();
+    fn on_enter(&mut self, ctx: &mut Context) -> GameResult<()> {
+        self.load_data(|path| read_resource(ctx, path))
+    }
+
+    fn on_exit(&mut self, _ctx: &mut Context) -> GameResult<()> {
+        let outcome = self.outcome();
+        if let InputSource::Record { path, replay } = &mut self.input_source {
+            replay.outcome = outcome;
+            replay.save(&path)?;
+            eprintln!("Replay saved to {}", path.display());
+        }
         Ok(())
     }
+
+    fn gamepad_button_down_event(&mut self, _ctx: &mut Context, btn: Button) {
+        self.gamepad.button_down(btn);
+    }
+
+    fn gamepad_button_up_event(&mut self, _ctx: &mut Context, btn: Button) {
+        self.gamepad.button_up(btn);
+    }
+
+    fn gamepad_axis_event(&mut self, _ctx: &mut Context, axis: Axis, value: f32) {
+        self.gamepad.axis_changed(axis, value);
+    }
 }
@@ -0,0 +1,126 @@
+use std::path::PathBuf;
+
+use common::replay::{Replay, ReplayPlayer};
+
+/// Where each frame's input comes from.
+#[derive(Default)]
+pub enum InputSource {
+    #[default]
+    Live,
+    Record {
+        path: PathBuf,
+        replay: Replay,
+    },
+    Playback(ReplayPlayer),
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::io::ErrorKind;
+    use std::path::{Path, PathBuf};
+    use std::time::Duration;
+
+    use common::game_transform::GameTransform;
+    use common::input::InputState;
+    use common::replay::Replay;
+    use ggez::event::{KeyCode, MouseButton};
+    use ggez::GameResult;
+
+    use crate::space_shooter::{tag, SpaceGame};
+
+    const REPLAYS: &str = "resources/replays";
+    const BOT_SESSION: &str = "resources/replays/bot_session.replay";
+
+    fn manifest_path(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join(name)
+    }
+
+    /// Reads data files from `resources` the way the game mounts it.
+    fn read_resource(path: &str) -> GameResult<Option<String>> {
+        let path = manifest_path("resources").join(path.trim_start_matches('/'));
+        match fs::read_to_string(path) {
+            Ok(contents) => Ok(Some(contents)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn new_game(seed: u64) -> SpaceGame {
+        let mut game = SpaceGame::new(seed);
+        game.load_data(read_resource).unwrap();
+        game
+    }
+
+    fn play(path: &Path) {
+        let replay = Replay::load(path).unwrap();
+        let mut game = new_game(replay.seed);
+        for frame in replay.frames {
+            game.step(frame.delta, frame.input).unwrap();
+        }
+
+        assert_eq!(game.outcome(), replay.outcome, "{}", path.display());
+    }
+
+    #[test]
+    fn test_recorded_replays() {
+        let mut replays: Vec<PathBuf> = fs::read_dir(manifest_path(REPLAYS))
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "replay"))
+            .collect();
+        replays.sort();
+        assert!(!replays.is_empty());
+        for path in replays {
+            play(&path);
+        }
+    }
+
+    /// Re-records the bot session after an intended gameplay change with
+    /// `cargo test record_bot_session -- --ignored`. Anything else that breaks
+    /// the replay is a regression, not a reason to record it again.
+    #[test]
+    #[ignore]
+    fn record_bot_session() {
+        let seed = 4300;
+        let delta = Duration::from_nanos(16_666_667);
+        let keys = [KeyCode::D, KeyCode::S, KeyCode::A, KeyCode::W];
+        let mut game = new_game(seed);
+        let mut replay = Replay::new(seed);
+
+        for frame in 0..1200usize {
+            let mut input = InputState::default();
+            input.keys.insert(keys[(frame / 45) % keys.len()]);
+            if frame % 300 == 100 {
+                input.keys.insert(KeyCode::LShift);
+            }
+
+            // Aims at the nearest enemy, so which one does not depend on
+            // entity ids and new entities elsewhere leave the session alone.
+            let player = game
+                .entity_manager
+                .get_entities_with_tag::<tag::Player>()
+                .first()
+                .and_then(|e| e.get_component::<GameTransform>())
+                .map(|t| t.position);
+            let target = player.and_then(|player| {
+                game.entity_manager
+                    .get_entities_with_tag::<tag::Enemy>()
+                    .into_iter()
+                    .filter_map(|e| e.get_component::<GameTransform>())
+                    .map(|t| t.position)
+                    .min_by(|a, b| a.distance(player).total_cmp(&b.distance(player)))
+            });
+            if let Some(target) = target {
+                input.mouse_position = game.camera.world_to_screen(target);
+                input.mouse_buttons.insert(MouseButton::Left);
+            }
+
+            replay.push(delta, input.clone());
+            game.step(delta, input).unwrap();
+        }
+
+        replay.outcome = game.outcome();
+        replay.save(manifest_path(BOT_SESSION)).unwrap();
+    }
+}
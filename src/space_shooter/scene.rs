@@ -0,0 +1,205 @@
+use common::event::{EventReceiver, EventSystem};
+use common::input::{ActionMap, Binding};
+use ecs::entity::EntityId;
+use ecs::manager::EntityManager;
+use ggez::event::{Button as PadButton, KeyCode};
+use ggez::graphics::{Color, DrawMode, Mesh};
+use ggez::{Context, GameResult};
+
+use crate::game::Game;
+use crate::scene::{Scene, Transition};
+use crate::space_shooter::input::Action;
+use crate::space_shooter::{tag, GameOptions};
+use crate::ui::{
+    add_widget, render_ui_system, ui_interaction_system, Anchor, Button, Direction, Label, Stack,
+    Ui, UiEvent, UiNode,
+};
+
+/// Vertical stack in the middle of the screen the scene's widgets go in.
+fn add_menu(manager: &mut EntityManager) -> EntityId {
+    manager
+        .add_tag(tag::Ui)
+        .add_component(UiNode::new(Anchor::Center))
+        .add_component(Stack {
+            direction: Direction::Vertical,
+            spacing: 10f32,
+        })
+        .id
+}
+
+fn add_button(manager: &mut EntityManager, menu: EntityId, title: &str) -> EntityId {
+    let button = Button {
+        title: title.to_string(),
+        size: 40f32,
+    };
+    add_widget(manager, menu, button)
+}
+
+fn add_label(manager: &mut EntityManager, menu: EntityId, text: String, size: f32) {
+    add_widget(manager, menu, Label { text, size });
+}
+
+/// Overlay shown over a frozen `SpaceGame` until the pause action is pressed
+/// again or the run is ended.
+pub struct PauseScene {
+    entity_manager: EntityManager,
+    event_system: EventSystem,
+    ui: Ui,
+    bindings: ActionMap<Action>,
+    options: GameOptions,
+    score: i32,
+    resume_button: EntityId,
+    end_button: EntityId,
+    resume: bool,
+}
+
+impl PauseScene {
+    pub fn new(score: i32, bindings: ActionMap<Action>, options: GameOptions) -> Self {
+        let mut entity_manager = EntityManager::new();
+        let menu = add_menu(&mut entity_manager);
+        add_label(&mut entity_manager, menu, "Paused".to_string(), 60f32);
+        let resume_button = add_button(&mut entity_manager, menu, "Resume");
+        let end_button = add_button(&mut entity_manager, menu, "End Run");
+        entity_manager.update();
+        let mut ui = Ui::default();
+        ui.set_focus(Some(resume_button));
+
+        Self {
+            entity_manager,
+            event_system: EventSystem::default(),
+            ui,
+            bindings,
+            options,
+            score,
+            resume_button,
+            end_button,
+            resume: false,
+        }
+    }
+
+    fn is_pause(&self, binding: Binding) -> bool {
+        self.bindings.bindings(Action::Pause).contains(&binding)
+    }
+}
+
+impl Scene for PauseScene {
+    fn update(&mut self, ctx: &mut Context) -> GameResult<Transition> {
+        ui_interaction_system(
+            &mut self.entity_manager,
+            &mut self.ui,
+            ctx,
+            &mut self.event_system,
+        )?;
+
+        let events: Vec<UiEvent> = self.event_system.read();
+        for event in events {
+            match event {
+                UiEvent::Clicked(id) if id == self.end_button => {
+                    let game_over = GameOverScene::new(self.score, self.options.clone());
+                    return Ok(Transition::Reset(Box::new(game_over)));
+                }
+                UiEvent::Clicked(id) => self.resume |= id == self.resume_button,
+                _ => {}
+            }
+        }
+
+        if self.resume {
+            return Ok(Transition::Pop);
+        }
+        Ok(Transition::None)
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        let screen = ggez::graphics::screen_coordinates(ctx);
+        let shade = Mesh::new_rectangle(
+            ctx,
+            DrawMode::fill(),
+            screen,
+            Color::new(1f32, 1f32, 1f32, 0.6),
+        )?;
+        ggez::graphics::draw(ctx, &shade, ([0f32, 0f32], Color::WHITE))?;
+
+        render_ui_system(&mut self.entity_manager, ctx)
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+
+    fn ui(&mut self) -> Option<&mut Ui> {
+        Some(&mut self.ui)
+    }
+
+    fn key_down_event(&mut self, _ctx: &mut Context, keycode: KeyCode, repeat: bool) {
+        if !repeat && self.is_pause(Binding::Key(keycode)) {
+            self.resume = true;
+        }
+    }
+
+    fn gamepad_button_down_event(&mut self, _ctx: &mut Context, btn: PadButton) {
+        if self.is_pause(Binding::Gamepad(btn)) {
+            self.resume = true;
+        }
+    }
+}
+
+/// Final score of a run with a way back to the main menu.
+pub struct GameOverScene {
+    entity_manager: EntityManager,
+    event_system: EventSystem,
+    ui: Ui,
+    options: GameOptions,
+    menu_button: EntityId,
+}
+
+impl GameOverScene {
+    pub fn new(score: i32, options: GameOptions) -> Self {
+        let mut entity_manager = EntityManager::new();
+        let menu = add_menu(&mut entity_manager);
+        add_label(&mut entity_manager, menu, "Game Over".to_string(), 60f32);
+        add_label(
+            &mut entity_manager,
+            menu,
+            format!("Score: {}", score),
+            40f32,
+        );
+        let menu_button = add_button(&mut entity_manager, menu, "Main Menu");
+        entity_manager.update();
+        let mut ui = Ui::default();
+        ui.set_focus(Some(menu_button));
+
+        Self {
+            entity_manager,
+            event_system: EventSystem::default(),
+            ui,
+            options,
+            menu_button,
+        }
+    }
+}
+
+impl Scene for GameOverScene {
+    fn update(&mut self, ctx: &mut Context) -> GameResult<Transition> {
+        ui_interaction_system(
+            &mut self.entity_manager,
+            &mut self.ui,
+            ctx,
+            &mut self.event_system,
+        )?;
+
+        let events: Vec<UiEvent> = self.event_system.read();
+        if events.contains(&UiEvent::Clicked(self.menu_button)) {
+            let menu = Game::new(self.options.clone());
+            return Ok(Transition::Switch(Box::new(menu)));
+        }
+        Ok(Transition::None)
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        render_ui_system(&mut self.entity_manager, ctx)
+    }
+
+    fn ui(&mut self) -> Option<&mut Ui> {
+        Some(&mut self.ui)
+    }
+}
@@ -0,0 +1,59 @@
+use common::camera::Camera;
+use common::event::EventReceiver;
+use common::game_transform::{GameTransform, TryGet};
+use common::time::GameTime;
+use ecs::manager::EntityManager;
+use ggez::GameResult;
+
+use crate::space_shooter::component;
+use crate::space_shooter::component::constant::{
+    CAMERA_FOLLOW_SPEED, ENEMY_KILLED_TRAUMA, PLAYER_DIED_TRAUMA,
+};
+use crate::space_shooter::component::physics::Confined;
+use crate::space_shooter::system::{EnemyKilled, PlayerDied};
+use crate::space_shooter::tag;
+
+pub fn camera_follow_system(
+    manager: &mut EntityManager,
+    camera: &mut Camera,
+    time: &GameTime,
+) -> GameResult<()> {
+    let arenas = component::arenas(manager);
+    if let Some(player) = manager.get_entities_with_tag::<tag::Player>().first() {
+        let target = player.try_get_component::<GameTransform>()?.position;
+        camera.follow(target, CAMERA_FOLLOW_SPEED, time);
+
+        if let Some(&Confined(arena)) = player.get_component::<Confined>() {
+            if let Some((_, bounds)) = arenas.into_iter().find(|(id, _)| *id == arena) {
+                camera.clamp_to(bounds);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn on_screen(camera: &Camera, transform: &GameTransform) -> bool {
+    let point = camera.world_to_screen(transform.position);
+    (0f32..=camera.screen_size.x).contains(&point.x)
+        && (0f32..=camera.screen_size.y).contains(&point.y)
+}
+
+/// Shakes the camera for every kill and death the player can see.
+pub fn camera_shake_system<E>(events: &mut E, camera: &mut Camera, time: &GameTime)
+where
+    E: EventReceiver<EnemyKilled> + EventReceiver<PlayerDied>,
+{
+    let killed: Vec<EnemyKilled> = events.read();
+    let died: Vec<PlayerDied> = events.read();
+    for EnemyKilled(transform, _) in &killed {
+        if on_screen(camera, transform) {
+            camera.add_trauma(ENEMY_KILLED_TRAUMA);
+        }
+    }
+    for PlayerDied(transform) in &died {
+        if on_screen(camera, transform) {
+            camera.add_trauma(PLAYER_DIED_TRAUMA);
+        }
+    }
+    camera.update_shake(time);
+}
@@ -1,79 +1,491 @@
-use crate::space_shooter::component::game::Scoreboard;
-use crate::space_shooter::component::physics::Collider;
+use crate::space_shooter::component;
+use crate::space_shooter::component::movement::Speed;
+use crate::space_shooter::component::physics::{self, Collider, Confined, FastMoving, Solid};
 use crate::space_shooter::system::BoundCollide;
-use crate::space_shooter::{component, tag};
-use crate::{WINDOWS_HEIGHT, WINDOWS_WIDTH};
-use common::event::EventSender;
+use common::event::{EventReceiver, EventSender};
 use common::game_transform::{GameTransform, TryGet};
-use common::math::collision::BoxCollision;
+use common::math::collision::{CollisionShape, Contact, RayHit};
+use common::math::Vec2;
+use common::physics::{
+    Collision, CollisionEnded, CollisionOngoing, CollisionStarted, ContactTracker,
+};
+use common::spatial::SpatialGrid;
+use common::time::GameTime;
+use ecs::entity::EntityId;
 use ecs::manager::EntityManager;
+use ggez::graphics::Rect;
 use ggez::GameResult;
+use std::any::Any;
+use std::collections::HashMap;
 
 pub enum BoundAxis {
     X,
     Y,
 }
 
-pub fn windows_bound_collision_system<E: EventSender<BoundCollide>>(
+/// Which edge of `bounds` the collider crosses, with the coordinate on that
+/// axis that puts it back inside.
+fn bound_hit(collider: &Collider, bounds: Rect) -> Option<(BoundAxis, f32)> {
+    if collider.center.x - collider.radius <= bounds.left() {
+        Some((BoundAxis::X, bounds.left() + collider.radius))
+    } else if collider.center.x + collider.radius >= bounds.right() {
+        Some((BoundAxis::X, bounds.right() - collider.radius))
+    } else if collider.center.y - collider.radius <= bounds.top() {
+        Some((BoundAxis::Y, bounds.top() + collider.radius))
+    } else if collider.center.y + collider.radius >= bounds.bottom() {
+        Some((BoundAxis::Y, bounds.bottom() - collider.radius))
+    } else {
+        None
+    }
+}
+
+pub fn arena_bound_collision_system<E: EventSender<BoundCollide>>(
     manager: &mut EntityManager,
     event_system: &mut E,
 ) -> GameResult<()> {
-    let enemies = manager.get_entities_with_tag_mut::<tag::Enemy>();
-    for enemy in enemies {
-        let collider = enemy.try_get_component::<Collider>()?;
-
-        let detect = if collider.center.x - collider.radius <= 0f32 {
-            Some((BoundAxis::X, 0f32 + collider.radius))
-        } else if collider.center.x + collider.radius >= WINDOWS_WIDTH {
-            Some((BoundAxis::X, WINDOWS_WIDTH - collider.radius))
-        } else if collider.center.y - collider.radius <= 0f32 {
-            Some((BoundAxis::Y, 0f32 + collider.radius))
-        } else if collider.center.y + collider.radius >= WINDOWS_HEIGHT {
-            Some((BoundAxis::Y, 0f32 + WINDOWS_HEIGHT - collider.radius))
-        } else {
-            None
+    let arenas: HashMap<EntityId, Rect> = component::arenas(manager).into_iter().collect();
+    let confined = manager.get_entities_with_tag_mut::<Confined>();
+    for entity in confined {
+        let &Confined(arena) = entity.try_get_component::<Confined>()?;
+        let bounds = match arenas.get(&arena) {
+            Some(bounds) => *bounds,
+            None => continue,
         };
+        let collider = entity.try_get_component::<Collider>()?;
 
-        if let Some((bound, reset_pos)) = detect {
-            let position = &mut enemy.try_get_component_mut::<GameTransform>()?.position;
+        if let Some((bound, reset_pos)) = bound_hit(collider, bounds) {
+            let position = &mut entity.try_get_component_mut::<GameTransform>()?.position;
             match bound {
                 BoundAxis::X => position.x = reset_pos,
                 BoundAxis::Y => position.y = reset_pos,
             }
-            event_system.send(BoundCollide(enemy.id, bound));
+            event_system.send(BoundCollide(entity.id, bound));
         }
     }
     Ok(())
 }
 
-pub fn player_collision_system(manager: &mut EntityManager) -> GameResult<()> {
-    const DEATH_PENALTY: i32 = 500;
-
-    let players = manager.get_entities_with_tag::<tag::Player>();
-    let player = players.first().unwrap();
-    let &collider = player.try_get_component::<Collider>()?;
-    let enemies = manager.get_entities_with_tag_mut::<tag::Enemy>();
-    let mut collided = false;
-
-    for enemy in enemies {
-        if let Some(&enemy_collider) = enemy.get_component::<Collider>() {
-            let enemy_collision: BoxCollision = enemy_collider.into();
-            if enemy_collision.collide_aabb(&collider.into()) {
-                collided = true;
-                enemy.destroy();
-                break;
+/// Refills the broad phase with the bounds of every live collider.
+pub fn broad_phase_system(
+    manager: &mut EntityManager,
+    broad_phase: &mut SpatialGrid<EntityId>,
+) -> GameResult<()> {
+    let mut colliders: Vec<(EntityId, Rect)> = manager
+        .query_entities_component_mut::<Collider>()
+        .into_iter()
+        .map(|(id, collider)| (id, collider.bounds()))
+        .collect();
+    colliders.sort_by_key(|(id, _)| *id);
+
+    broad_phase.clear();
+    for (id, bounds) in colliders {
+        broad_phase.insert(id, bounds);
+    }
+    Ok(())
+}
+
+/// Narrow phase over the broad phase pairs, reported as collision events.
+/// Fast moving entities are also swept over the motion that brought them
+/// here, so they hit what they would otherwise have jumped over.
+pub fn collision_event_system<E>(
+    manager: &mut EntityManager,
+    broad_phase: &SpatialGrid<EntityId>,
+    tracker: &mut ContactTracker,
+    time: &GameTime,
+    events: &mut E,
+) -> GameResult<()>
+where
+    E: EventSender<CollisionStarted>
+        + EventSender<CollisionOngoing>
+        + EventSender<CollisionEnded>
+        + EventReceiver<CollisionStarted>
+        + EventReceiver<CollisionOngoing>
+        + EventReceiver<CollisionEnded>,
+{
+    let mut colliders = HashMap::new();
+    for entity in manager.get_entities_with_tag::<Collider>() {
+        if entity.is_alive() {
+            let collider = *entity.try_get_component::<Collider>()?;
+            colliders.insert(entity.id, (collider, physics::collision_shape(entity)?));
+        }
+    }
+
+    let mut motions = Vec::new();
+    for entity in manager.get_entities_with_tag::<FastMoving>() {
+        if let (true, Some(speed)) = (entity.is_alive(), entity.get_component::<Speed>()) {
+            motions.push((entity.id, speed.velocity * time.delta().as_secs_f32()));
+        }
+    }
+    motions.sort_by_key(|(id, _)| *id);
+
+    let mut collisions: Vec<Collision> = broad_phase
+        .overlapping_pairs()
+        .into_iter()
+        .filter_map(|(a, b)| {
+            let (a_collider, a_shape) = colliders.get(&a)?;
+            let (b_collider, b_shape) = colliders.get(&b)?;
+            if !a_collider.interacts(b_collider) {
+                return None;
             }
+            let contact = a_shape.contact(b_shape)?;
+            Some(Collision::new(a, b, contact))
+        })
+        .collect();
+    for (id, motion) in motions {
+        if let Some(collision) = first_hit(&colliders, broad_phase, id, motion) {
+            collisions.push(collision);
         }
     }
+    tracker.update(collisions, events);
+    Ok(())
+}
 
-    if collided {
-        let mut players = manager.get_entities_with_tag_mut::<tag::Player>();
-        players.first_mut().unwrap().destroy();
-        component::create_player(manager);
+/// Earliest collider that `id` ran into while moving by `motion` to where it
+/// is now.
+fn first_hit(
+    colliders: &HashMap<EntityId, (Collider, CollisionShape)>,
+    broad_phase: &SpatialGrid<EntityId>,
+    id: EntityId,
+    motion: Vec2,
+) -> Option<Collision> {
+    let (collider, shape) = colliders.get(&id)?;
+    let previous = Collider {
+        center: collider.center - motion,
+        ..*collider
+    };
+    let swept_shape = shape.translated(-motion);
+    broad_phase
+        .query_rect(collider.bounds().combine_with(previous.bounds()))
+        .into_iter()
+        .filter(|&other| other != id)
+        .filter_map(|other| {
+            let (other_collider, other_shape) = colliders.get(&other)?;
+            if !collider.interacts(other_collider) {
+                return None;
+            }
+            let sweep = swept_shape.sweep(motion, other_shape)?;
+            Some((other, sweep))
+        })
+        .min_by(|a, b| a.1.time.total_cmp(&b.1.time))
+        .map(|(other, sweep)| {
+            let contact = Contact {
+                normal: sweep.normal,
+                depth: 0f32,
+                point: sweep.point,
+            };
+            Collision::new(id, other, contact)
+        })
+}
 
-        let mut scoreboard = manager.query_entities_component_mut::<Scoreboard>();
-        scoreboard.first_mut().unwrap().1.current_score -= DEATH_PENALTY;
+/// First live collider on one of the `mask` layers that the ray enters, and
+/// where it does.
+pub fn raycast(
+    manager: &mut EntityManager,
+    broad_phase: &SpatialGrid<EntityId>,
+    origin: Vec2,
+    direction: Vec2,
+    max_distance: f32,
+    mask: u32,
+) -> GameResult<Option<(EntityId, RayHit)>> {
+    let mut first: Option<(EntityId, RayHit)> = None;
+    for (id, bounds_distance) in broad_phase.raycast(origin, direction, max_distance) {
+        // Candidates come nearest bounds first, nothing further can be closer.
+        if first.is_some_and(|(_, hit)| bounds_distance > hit.distance) {
+            break;
+        }
+        let entity = match manager.get_entity(id) {
+            Some(entity) if entity.is_alive() => entity,
+            _ => continue,
+        };
+        if entity
+            .get_component::<Collider>()
+            .is_none_or(|collider| collider.layer & mask == 0)
+        {
+            continue;
+        }
+        let hit = physics::collision_shape(entity)?.raycast(origin, direction, max_distance);
+        if let Some(hit) = hit {
+            if first.is_none_or(|(_, first)| hit.distance < first.distance) {
+                first = Some((id, hit));
+            }
+        }
     }
+    Ok(first)
+}
+
+fn alive_with<T: Any>(manager: &mut EntityManager, id: EntityId) -> bool {
+    manager
+        .get_entity(id)
+        .is_some_and(|entity| entity.is_alive() && entity.has_component::<T>())
+}
+
+/// Orders the pair as (entity with `A`, entity with `B`), when both are
+/// still alive.
+pub fn tagged_pair<A: Any, B: Any>(
+    manager: &mut EntityManager,
+    collision: &Collision,
+) -> Option<(EntityId, EntityId)> {
+    let (a, b) = (collision.a, collision.b);
+    if alive_with::<A>(manager, a) && alive_with::<B>(manager, b) {
+        Some((a, b))
+    } else if alive_with::<A>(manager, b) && alive_with::<B>(manager, a) {
+        Some((b, a))
+    } else {
+        None
+    }
+}
+
+struct Body {
+    solid: Solid,
+    velocity: Option<Vec2>,
+}
+
+/// Velocity change of a body bouncing off a wall with `normal` pointing into
+/// the wall.
+fn bounce(velocity: Vec2, normal: Vec2) -> Vec2 {
+    if velocity.dot(normal) > 0f32 {
+        velocity.reflect(normal) - velocity
+    } else {
+        Vec2::zero()
+    }
+}
 
+/// Pushes touching solid entities apart and bounces their velocities off
+/// the contact normal. Dynamic bodies of equal weight share the push and
+/// swap their speeds along the normal.
+pub fn collision_resolution_system<E>(manager: &mut EntityManager, events: &E) -> GameResult<()>
+where
+    E: EventReceiver<CollisionStarted> + EventReceiver<CollisionOngoing>,
+{
+    let started: Vec<&CollisionStarted> = events.peek();
+    let ongoing: Vec<&CollisionOngoing> = events.peek();
+    let collisions = started
+        .into_iter()
+        .map(|e| e.0)
+        .chain(ongoing.into_iter().map(|e| e.0));
+
+    let mut bodies = HashMap::new();
+    for entity in manager.get_entities_with_tag::<Solid>() {
+        let trigger = entity
+            .get_component::<Collider>()
+            .is_none_or(|collider| collider.trigger);
+        if entity.is_alive() && !trigger {
+            let body = Body {
+                solid: *entity.try_get_component::<Solid>()?,
+                velocity: entity.get_component::<Speed>().map(|speed| speed.velocity),
+            };
+            bodies.insert(entity.id, body);
+        }
+    }
+
+    let mut pushes: HashMap<EntityId, (Vec2, Vec2)> = HashMap::new();
+    for Collision {
+        a: a_id,
+        b: b_id,
+        contact,
+    } in collisions
+    {
+        let (a, b) = match (bodies.get(&a_id), bodies.get(&b_id)) {
+            (Some(a), Some(b)) => (a, b),
+            _ => continue,
+        };
+        let normal = contact.normal;
+        let (a_push, b_push, a_bounce, b_bounce) = match (a.solid, b.solid) {
+            (Solid::Static, Solid::Static) => continue,
+            (Solid::Dynamic, Solid::Static) => (
+                normal * -contact.depth,
+                Vec2::zero(),
+                a.velocity.map_or(Vec2::zero(), |v| bounce(v, normal)),
+                Vec2::zero(),
+            ),
+            (Solid::Static, Solid::Dynamic) => (
+                Vec2::zero(),
+                normal * contact.depth,
+                Vec2::zero(),
+                b.velocity.map_or(Vec2::zero(), |v| bounce(v, -normal)),
+            ),
+            (Solid::Dynamic, Solid::Dynamic) => {
+                let half = contact.depth / 2f32;
+                let (va, vb) = (
+                    a.velocity.unwrap_or_default(),
+                    b.velocity.unwrap_or_default(),
+                );
+                let closing = (vb - va).dot(normal);
+                let exchange = if closing < 0f32 {
+                    normal * closing
+                } else {
+                    Vec2::zero()
+                };
+                (normal * -half, normal * half, exchange, -exchange)
+            }
+        };
+
+        let entry = pushes.entry(a_id).or_default();
+        *entry = (entry.0 + a_push, entry.1 + a_bounce);
+        let entry = pushes.entry(b_id).or_default();
+        *entry = (entry.0 + b_push, entry.1 + b_bounce);
+    }
+
+    for (id, (push, bounce)) in pushes {
+        let entity = match manager.get_entity(id) {
+            Some(entity) => entity,
+            None => continue,
+        };
+        let transform = entity.try_get_component_mut::<GameTransform>()?;
+        transform.position += push;
+        let collider = entity.try_get_component_mut::<Collider>()?;
+        collider.center += push;
+        if let Some(speed) = entity.get_component_mut::<Speed>() {
+            speed.velocity += bounce;
+        }
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use common::event::{EventReceiver, EventSystem};
+    use common::game_transform::GameTransform;
+    use common::math::Vec2;
+    use common::physics::{CollisionStarted, ContactTracker};
+    use common::spatial::SpatialGrid;
+    use common::time::GameTime;
+    use ecs::manager::EntityManager;
+    use ggez::graphics::Rect;
+
+    use super::{
+        bounce, bound_hit, broad_phase_system, collision_event_system, raycast, BoundAxis,
+    };
+    use crate::space_shooter::component::constant::{BULLET_SPEED, ENEMY_SIZE};
+    use crate::space_shooter::component::create_projectile;
+    use crate::space_shooter::component::physics::{layer, Collider};
+    use crate::space_shooter::component::weapon::{ProjectileTemplate, PLAYER_BULLET};
+    use crate::space_shooter::system::movement;
+    use crate::space_shooter::tag;
+
+    fn collider(x: f32, y: f32) -> Collider {
+        Collider {
+            center: Vec2::new(x, y),
+            radius: 10f32,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_bound_hit_inside() {
+        let bounds = Rect::new(-100f32, 50f32, 400f32, 300f32);
+        assert!(bound_hit(&collider(100f32, 200f32), bounds).is_none());
+    }
+
+    #[test]
+    fn test_bound_hit_edges() {
+        let bounds = Rect::new(-100f32, 50f32, 400f32, 300f32);
+        assert!(matches!(
+            bound_hit(&collider(-95f32, 200f32), bounds),
+            Some((BoundAxis::X, x)) if x == -90f32
+        ));
+        assert!(matches!(
+            bound_hit(&collider(295f32, 200f32), bounds),
+            Some((BoundAxis::X, x)) if x == 290f32
+        ));
+        assert!(matches!(
+            bound_hit(&collider(100f32, 345f32), bounds),
+            Some((BoundAxis::Y, y)) if y == 340f32
+        ));
+    }
+
+    #[test]
+    fn test_bounce_reflects_approaching_velocity() {
+        let normal = Vec2::new(1f32, 0f32);
+        let velocity = Vec2::new(3f32, 2f32);
+        assert_eq!(velocity + bounce(velocity, normal), Vec2::new(-3f32, 2f32));
+        assert_eq!(bounce(Vec2::new(-3f32, 2f32), normal), Vec2::zero());
+    }
+
+    #[test]
+    fn test_fast_bullet_hits_enemy_at_10_fps() {
+        let mut manager = EntityManager::new();
+        let mut events = EventSystem::default();
+        let mut grid = SpatialGrid::default();
+        let mut tracker = ContactTracker::default();
+        let mut time = GameTime::default();
+
+        let enemy = manager
+            .add_tag(tag::Enemy)
+            .add_component(Collider {
+                center: Vec2::new(200f32, 0f32),
+                radius: ENEMY_SIZE,
+                rotation: 0f32,
+                layer: layer::ENEMY,
+                mask: layer::BULLET,
+                trigger: false,
+            })
+            .id;
+        // 120 px a frame, stepping from x = 150 to 270 over the enemy.
+        let transform = GameTransform::new(Vec2::new(30f32, 0f32));
+        let fast = ProjectileTemplate {
+            speed: BULLET_SPEED * 3f32,
+            ..PLAYER_BULLET
+        };
+        let bullet = create_projectile(&mut manager, &fast, transform).id;
+
+        let mut hits = Vec::new();
+        for _ in 0..3 {
+            manager.update();
+            time.advance(Duration::from_millis(100));
+            movement::integration_system(&mut manager, &time).unwrap();
+            movement::collider_follow_transform_system(&mut manager).unwrap();
+            broad_phase_system(&mut manager, &mut grid).unwrap();
+            collision_event_system(&mut manager, &grid, &mut tracker, &time, &mut events).unwrap();
+            let started: Vec<&CollisionStarted> = events.peek();
+            hits.extend(started.iter().map(|e| (e.0.a, e.0.b)));
+        }
+
+        assert_eq!(hits, vec![(enemy, bullet)]);
+    }
+
+    #[test]
+    fn test_raycast_first_hit_on_mask() {
+        let mut manager = EntityManager::new();
+        let mut grid = SpatialGrid::default();
+        let mut add = |x: f32, layer: u32| {
+            manager
+                .add()
+                .add_component(Collider {
+                    center: Vec2::new(x, 0f32),
+                    radius: 20f32,
+                    rotation: 0f32,
+                    layer,
+                    ..Default::default()
+                })
+                .id
+        };
+        let near = add(100f32, layer::ENEMY);
+        let far = add(300f32, layer::OBSTACLE);
+        manager.update();
+        broad_phase_system(&mut manager, &mut grid).unwrap();
+
+        let cast = |manager: &mut EntityManager, mask: u32, max_distance: f32| {
+            raycast(
+                manager,
+                &grid,
+                Vec2::zero(),
+                Vec2::new(1f32, 0f32),
+                max_distance,
+                mask,
+            )
+            .unwrap()
+            .map(|(id, hit)| (id, hit.distance))
+        };
+        let all = layer::ENEMY | layer::OBSTACLE;
+        assert_eq!(cast(&mut manager, all, 1000f32), Some((near, 80f32)));
+        assert_eq!(
+            cast(&mut manager, layer::OBSTACLE, 1000f32),
+            Some((far, 280f32))
+        );
+        assert_eq!(cast(&mut manager, layer::OBSTACLE, 200f32), None);
+        assert_eq!(cast(&mut manager, layer::BULLET, 1000f32), None);
+    }
+}
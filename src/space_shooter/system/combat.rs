@@ -0,0 +1,350 @@
+use common::event::{EventReceiver, EventSender};
+use common::game_transform::{GameTransform, TryGet};
+use common::math::Vec2;
+use common::physics::{Collision, CollisionStarted};
+use common::time::GameTime;
+use ecs::entity::EntityId;
+use ecs::manager::EntityManager;
+use ggez::graphics::Rect;
+use ggez::{GameError, GameResult};
+
+use crate::space_shooter::component::constant::{
+    HIT_FLASH_TIME, RESPAWN_DELAY, SAFE_SPAWN_DISTANCE,
+};
+use crate::space_shooter::component::game::{Lives, Respawn, Scoreboard};
+use crate::space_shooter::component::general::{Damage, Health, HitFlash, Invulnerability, Score};
+use crate::space_shooter::component::physics::Confined;
+use crate::space_shooter::component::shape::Shape;
+use crate::space_shooter::component::weapon::Projectile;
+use crate::space_shooter::{component, tag};
+
+use super::collision::tagged_pair;
+use super::{EnemyKilled, PlayerDied};
+
+/// Deals `source`'s damage to `target` unless it is invulnerable.
+fn hit(manager: &mut EntityManager, source: EntityId, target: EntityId) {
+    let damage = match manager
+        .get_entity(source)
+        .and_then(|entity| entity.get_component::<Damage>())
+    {
+        Some(&Damage(damage)) => damage,
+        None => return,
+    };
+    let target = match manager.get_entity(target) {
+        Some(target) => target,
+        None => return,
+    };
+    if let Some(invulnerability) = target.get_component_mut::<Invulnerability>() {
+        if invulnerability.active() {
+            return;
+        }
+        invulnerability.time_left = invulnerability.grace;
+    }
+    if let Some(health) = target.get_component_mut::<Health>() {
+        health.take(damage);
+    }
+    if let Some(flash) = target.get_component_mut::<HitFlash>() {
+        flash.time_left = HIT_FLASH_TIME;
+    }
+}
+
+/// A projectile passes through as many targets as it can pierce.
+fn spend(manager: &mut EntityManager, projectile: EntityId) {
+    if let Some(entity) = manager.get_entity(projectile) {
+        match entity.get_component_mut::<Projectile>() {
+            Some(projectile) if projectile.pierce > 0 => projectile.pierce -= 1,
+            _ => entity.destroy(),
+        }
+    }
+}
+
+/// Bullets hurt what they hit, the player and enemies hurt each other on
+/// contact.
+pub fn damage_system<E>(manager: &mut EntityManager, events: &E) -> GameResult<()>
+where
+    E: EventReceiver<CollisionStarted>,
+{
+    let started: Vec<Collision> = events.peek().into_iter().map(|e| e.0).collect();
+    for collision in &started {
+        if let Some((enemy, bullet)) = tagged_pair::<tag::Enemy, tag::Bullet>(manager, collision) {
+            hit(manager, bullet, enemy);
+            spend(manager, bullet);
+        } else if let Some((player, bullet)) =
+            tagged_pair::<tag::Player, tag::Bullet>(manager, collision)
+        {
+            hit(manager, bullet, player);
+            spend(manager, bullet);
+        } else if let Some((player, enemy)) =
+            tagged_pair::<tag::Player, tag::Enemy>(manager, collision)
+        {
+            hit(manager, enemy, player);
+            hit(manager, player, enemy);
+        }
+    }
+    Ok(())
+}
+
+/// Counts down invulnerability and hit flashes.
+pub fn health_timer_system(manager: &mut EntityManager, time: &GameTime) -> GameResult<()> {
+    let dt = time.delta();
+    for (_, invulnerability) in manager.query_entities_component_mut::<Invulnerability>() {
+        invulnerability.time_left = invulnerability.time_left.saturating_sub(dt);
+    }
+    for (_, flash) in manager.query_entities_component_mut::<HitFlash>() {
+        flash.time_left = flash.time_left.saturating_sub(dt);
+    }
+    Ok(())
+}
+
+/// Removes enemies out of health, scoring them and sending `EnemyKilled`.
+pub fn enemy_death_system<E>(manager: &mut EntityManager, events: &mut E) -> GameResult<()>
+where
+    E: EventSender<EnemyKilled>,
+{
+    let mut sum_score = 0;
+    for enemy in manager.get_entities_with_tag_mut::<tag::Enemy>() {
+        let dead = enemy.get_component::<Health>().is_some_and(Health::is_dead);
+        if !enemy.is_alive() || !dead {
+            continue;
+        }
+        enemy.destroy();
+        sum_score += enemy.try_get_component::<Score>()?.0;
+        let transform = enemy.try_get_component::<GameTransform>()?.clone();
+        let split = if enemy.has_component::<tag::SmallEnemy>() {
+            None
+        } else {
+            enemy.get_component::<Shape>().copied()
+        };
+        events.send(EnemyKilled(transform, split));
+    }
+
+    let mut scoreboard = manager.query_entities_component_mut::<Scoreboard>();
+    scoreboard.first_mut().unwrap().1.current_score += sum_score;
+
+    Ok(())
+}
+
+/// Removes a player out of health and takes one of its lives. It comes back
+/// after `RESPAWN_DELAY` while it has lives left.
+pub fn player_death_system<E>(
+    manager: &mut EntityManager,
+    time: &GameTime,
+    events: &mut E,
+) -> GameResult<()>
+where
+    E: EventSender<PlayerDied>,
+{
+    let dead = manager
+        .get_entities_with_tag_mut::<tag::Player>()
+        .into_iter()
+        .find(|player| {
+            player.is_alive()
+                && player
+                    .get_component::<Health>()
+                    .is_some_and(Health::is_dead)
+        });
+    let player = match dead {
+        Some(player) => player,
+        None => return Ok(()),
+    };
+    player.destroy();
+    let transform = player.try_get_component::<GameTransform>()?.clone();
+    let &Confined(arena) = player.try_get_component::<Confined>()?;
+    events.send(PlayerDied(transform));
+
+    let mut lives = manager.query_entities_component_mut::<Lives>();
+    let (_, lives) = lives
+        .first_mut()
+        .ok_or_else(|| GameError::CustomError("Lives not found".to_string()))?;
+    lives.left = lives.left.saturating_sub(1);
+    if lives.left > 0 {
+        lives.respawn = Some(Respawn {
+            at: time.elapsed() + RESPAWN_DELAY,
+            arena,
+        });
+    }
+
+    Ok(())
+}
+
+/// Spot in `bounds` at least `clearance` away from every enemy, preferring
+/// the middle of the arena.
+fn safe_spawn(bounds: Rect, enemies: &[Vec2], clearance: f32) -> Option<Vec2> {
+    const STEPS: usize = 5;
+
+    let center = Vec2::new(bounds.x + bounds.w / 2f32, bounds.y + bounds.h / 2f32);
+    let mut spots: Vec<Vec2> = (0..STEPS * STEPS)
+        .map(|i| {
+            let x = ((i % STEPS) as f32 + 0.5) / STEPS as f32;
+            let y = ((i / STEPS) as f32 + 0.5) / STEPS as f32;
+            Vec2::new(bounds.x + bounds.w * x, bounds.y + bounds.h * y)
+        })
+        .collect();
+    spots.sort_by(|a, b| a.distance(center).total_cmp(&b.distance(center)));
+    spots.into_iter().find(|spot| {
+        enemies
+            .iter()
+            .all(|enemy| enemy.distance(*spot) >= clearance)
+    })
+}
+
+/// Brings the player back once its respawn is due, waiting for a spot clear
+/// of enemies.
+pub fn respawn_system(manager: &mut EntityManager, time: &GameTime) -> GameResult<()> {
+    let respawn = match manager.query_entities_component::<Lives>().first() {
+        Some(Lives {
+            respawn: Some(respawn),
+            ..
+        }) if time.elapsed() >= respawn.at => *respawn,
+        _ => return Ok(()),
+    };
+    let bounds = component::arenas(manager)
+        .into_iter()
+        .find_map(|(id, bounds)| (id == respawn.arena).then_some(bounds))
+        .ok_or_else(|| GameError::CustomError(format!("Arena {} not found", respawn.arena)))?;
+    let enemies: Vec<Vec2> = manager
+        .get_entities_with_tag::<tag::Enemy>()
+        .into_iter()
+        .filter_map(|enemy| enemy.get_component::<GameTransform>())
+        .map(|transform| transform.position)
+        .collect();
+
+    if let Some(position) = safe_spawn(bounds, &enemies, SAFE_SPAWN_DISTANCE) {
+        component::create_player(manager, respawn.arena, position);
+        for (_, lives) in manager.query_entities_component_mut::<Lives>() {
+            lives.respawn = None;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use common::event::{EventSender, EventSystem};
+    use common::math::collision::Contact;
+    use common::math::Vec2;
+    use common::physics::{Collision, CollisionStarted};
+    use common::time::GameTime;
+    use ecs::entity::EntityId;
+    use ecs::manager::EntityManager;
+
+    use ggez::graphics::Rect;
+
+    use super::{damage_system, health_timer_system, safe_spawn};
+    use crate::space_shooter::component::general::{Damage, Health, HitFlash, Invulnerability};
+    use crate::space_shooter::component::weapon::Projectile;
+    use crate::space_shooter::tag;
+
+    fn started(a: EntityId, b: EntityId) -> CollisionStarted {
+        CollisionStarted(Collision {
+            a,
+            b,
+            contact: Contact {
+                normal: Vec2::zero(),
+                depth: 0f32,
+                point: Vec2::zero(),
+            },
+        })
+    }
+
+    #[test]
+    fn test_invulnerable_after_hit() {
+        let mut manager = EntityManager::new();
+        let player = manager
+            .add_tag(tag::Player)
+            .add_component(Health::new(3))
+            .add_component(Invulnerability {
+                grace: Duration::from_secs(1),
+                time_left: Duration::ZERO,
+            })
+            .add_component(HitFlash::default())
+            .id;
+        let mut bullet = || {
+            manager
+                .add_tag(tag::Bullet)
+                .add_component(Damage(1))
+                .add_component(Projectile { pierce: 0 })
+                .id
+        };
+        let (first, second) = (bullet(), bullet());
+        manager.update();
+
+        let mut events = EventSystem::default();
+        events.send(started(player, first));
+        damage_system(&mut manager, &events).unwrap();
+        let mut events = EventSystem::default();
+        events.send(started(second, player));
+        damage_system(&mut manager, &events).unwrap();
+
+        let entity = manager.get_entity(player).unwrap();
+        assert_eq!(entity.get_component::<Health>().unwrap().current, 2);
+        assert!(entity.get_component::<HitFlash>().unwrap().time_left > Duration::ZERO);
+        // Both bullets are spent even though only one did damage.
+        assert!(!manager.get_entity(first).unwrap().is_alive());
+        assert!(!manager.get_entity(second).unwrap().is_alive());
+
+        let mut time = GameTime::default();
+        time.advance(Duration::from_secs(1));
+        health_timer_system(&mut manager, &time).unwrap();
+        let entity = manager.get_entity(player).unwrap();
+        assert!(!entity.get_component::<Invulnerability>().unwrap().active());
+        assert_eq!(
+            entity.get_component::<HitFlash>().unwrap().time_left,
+            Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn test_piercing_bullet_survives_hits() {
+        let mut manager = EntityManager::new();
+        let enemies: Vec<EntityId> = (0..3)
+            .map(|_| manager.add_tag(tag::Enemy).add_component(Health::new(2)).id)
+            .collect();
+        let bullet = manager
+            .add_tag(tag::Bullet)
+            .add_component(Damage(1))
+            .add_component(Projectile { pierce: 1 })
+            .id;
+        manager.update();
+
+        let mut events = EventSystem::default();
+        for &enemy in &enemies {
+            events.send(started(enemy, bullet));
+        }
+        damage_system(&mut manager, &events).unwrap();
+
+        let health: Vec<i32> = enemies
+            .iter()
+            .map(|&id| {
+                let entity = manager.get_entity(id).unwrap();
+                entity.get_component::<Health>().unwrap().current
+            })
+            .collect();
+        assert_eq!(health, [1, 1, 2]);
+        assert!(!manager.get_entity(bullet).unwrap().is_alive());
+    }
+
+    #[test]
+    fn test_safe_spawn() {
+        let bounds = Rect::new(0f32, 0f32, 1000f32, 1000f32);
+        let center = Vec2::new(500f32, 500f32);
+        assert_eq!(safe_spawn(bounds, &[], 100f32), Some(center));
+        assert_eq!(
+            safe_spawn(bounds, &[Vec2::new(900f32, 900f32)], 100f32),
+            Some(center)
+        );
+
+        let spot = safe_spawn(bounds, &[center], 150f32).unwrap();
+        assert!(spot.distance(center) >= 150f32);
+        // The nearest clear spot on the grid.
+        assert!((spot.distance(center) - 200f32).abs() < 1e-3);
+
+        let everywhere: Vec<Vec2> = (0..5)
+            .flat_map(|x| (0..5).map(move |y| Vec2::new(x as f32, y as f32) * 200f32))
+            .map(|corner| corner + Vec2::new(100f32, 100f32))
+            .collect();
+        assert!(safe_spawn(bounds, &everywhere, 50f32).is_none());
+    }
+}
@@ -1,71 +1,145 @@
-use common::event::EventSender;
+use common::camera::Camera;
+use common::event::EventReceiver;
 use common::game_transform::{GameTransform, TryGet};
+use common::input::ActionState;
+use common::time::GameTime;
 
 use crate::space_shooter::component;
-use crate::space_shooter::component::game::{Scoreboard, Spawner};
+use crate::space_shooter::component::game::Spawner;
 use crate::space_shooter::component::physics::Collider;
+use crate::space_shooter::input::{self, Action};
 use crate::space_shooter::tag;
 use ecs::manager::EntityManager;
 use ggez::graphics::{Color, DrawMode};
 use ggez::{Context, GameResult};
 use std::time::Duration;
 
-use crate::space_shooter::component::constant::BULLET_SPEED;
-use crate::space_shooter::component::create_bullet;
-use crate::space_shooter::component::general::{Lifespan, Score};
-use crate::space_shooter::component::movement::Speed;
-use common::math::collision::BoxCollision;
-use common::math::Vec2;
+use crate::space_shooter::component::constant::{ENEMY_FIRE_RANGE, LASER_RANGE, SMALL_ENEMY_SPEED};
+use crate::space_shooter::component::create_projectile;
+use crate::space_shooter::component::general::Lifespan;
+use crate::space_shooter::component::weapon::{ProjectileTemplate, SpecialWeapon, Weapon};
+use common::math::collision::RayHit;
+use common::math::random::{GameRng, RandomStream};
+use common::math::{Affine2, Vec2};
+use common::spatial::SpatialGrid;
 use ecs::entity::EntityId;
-use ggez::event::MouseButton;
+use rand::Rng;
 
+use super::collision;
 use super::EnemyKilled;
 
-pub fn enemy_spawner(manager: &mut EntityManager, ctx: &mut Context) -> GameResult<()> {
+pub fn enemy_spawner(
+    manager: &mut EntityManager,
+    rng: &mut GameRng,
+    time: &GameTime,
+) -> GameResult<()> {
     let enemy_count = manager.get_entities_with_tag::<tag::Enemy>().len();
 
     let mut spawner = manager.get_entities_with_tag_mut::<tag::Spawner>();
     let spawner = spawner.first_mut().unwrap();
 
     let info = spawner.try_get_component_mut::<Spawner>()?;
-    let delta = ggez::timer::delta(ctx);
-    info.last_spawned_duration += delta;
+    info.last_spawned_duration += time.delta();
 
     if enemy_count < info.max && info.last_spawned_duration >= info.interval {
         info.last_spawned_duration = Duration::from_secs(0);
-        component::create_enemy(manager);
+        let arenas = component::arenas(manager);
+        if !arenas.is_empty() {
+            let index = rng.stream(RandomStream::Spawn).gen_range(0..arenas.len());
+            let (arena, bounds) = arenas[index];
+            component::create_enemy(manager, rng, arena, bounds);
+        }
     }
     Ok(())
 }
 
-pub fn shoot_system(manager: &mut EntityManager, ctx: &mut Context) -> GameResult<()> {
-    let mut query = manager.query_entities_component_tag_mut::<Spawner, tag::Bullet>();
-    let spawner = query.first_mut().unwrap();
-    let dt = ggez::timer::delta(ctx);
-    let can_shoot = spawner.last_spawned_duration >= spawner.interval;
-    spawner.last_spawned_duration += dt;
-
-    if can_shoot && ggez::input::mouse::button_pressed(ctx, MouseButton::Left) {
-        spawner.last_spawned_duration = Duration::from_secs(0);
-
-        if let Some(player) = manager.get_entities_with_tag::<tag::Player>().first_mut() {
-            let mouse_pos: Vec2 = ggez::input::mouse::position(ctx).into();
-            let player_pos = player.try_get_component::<GameTransform>()?.position;
-            let shoot_dir = mouse_pos - player_pos;
-            let velocity = shoot_dir.normalized() * BULLET_SPEED;
-            let transform = GameTransform {
-                position: player_pos,
-                rotation: Vec2::zero(),
-            };
-            create_bullet(manager, Speed { velocity }, transform);
+/// Aims the player's weapon and holds its trigger while Fire is pressed.
+pub fn player_trigger_system(
+    manager: &mut EntityManager,
+    input: &ActionState<Action>,
+    camera: &Camera,
+) -> GameResult<()> {
+    for player in manager.get_entities_with_tag_mut::<tag::Player>() {
+        let player_pos = player.try_get_component::<GameTransform>()?.position;
+        let weapon = player.try_get_component_mut::<Weapon>()?;
+        let reach = weapon.projectile.speed;
+        weapon.aim = input::aim_target(input, camera, player_pos, reach) - player_pos;
+        weapon.trigger = input.pressed(Action::Fire);
+    }
+    Ok(())
+}
+
+/// Enemies aim at the player and fire while it is in range.
+pub fn enemy_trigger_system(manager: &mut EntityManager) -> GameResult<()> {
+    let player_pos = match manager.get_entities_with_tag::<tag::Player>().first() {
+        Some(player) => Some(player.try_get_component::<GameTransform>()?.position),
+        None => None,
+    };
+    for enemy in manager.get_entities_with_tag_mut::<tag::Enemy>() {
+        let enemy_pos = enemy.try_get_component::<GameTransform>()?.position;
+        if let Some(weapon) = enemy.get_component_mut::<Weapon>() {
+            weapon.aim = player_pos.map_or(Vec2::zero(), |pos| pos - enemy_pos);
+            weapon.trigger =
+                player_pos.is_some_and(|pos| pos.distance(enemy_pos) <= ENEMY_FIRE_RANGE);
         }
     }
     Ok(())
 }
 
-pub fn lifespan_system(manager: &mut EntityManager, ctx: &mut Context) -> GameResult<()> {
+/// Fires every weapon whose holder pulls the trigger.
+pub fn shoot_system(manager: &mut EntityManager, time: &GameTime) -> GameResult<()> {
+    let now = time.elapsed();
+    let mut shots = Vec::new();
+    for entity in manager.get_entities_with_tag_mut::<Weapon>() {
+        let position = entity.try_get_component::<GameTransform>()?.position;
+        let weapon = entity.try_get_component_mut::<Weapon>()?;
+        for angle in weapon.fire(now) {
+            shots.push((weapon.projectile, position, angle));
+        }
+    }
+    for (projectile, position, angle) in shots {
+        let transform = GameTransform::new(position).with_rotation(angle);
+        create_projectile(manager, &projectile, transform);
+    }
+    Ok(())
+}
+
+/// Fires the special weapon towards the aim once its cooldown is over.
+pub fn special_weapon_system(
+    manager: &mut EntityManager,
+    input: &ActionState<Action>,
+    camera: &Camera,
+    time: &GameTime,
+) -> GameResult<()> {
+    if !input.just_pressed(Action::Special) {
+        return Ok(());
+    }
+    let player_pos = match manager.get_entities_with_tag::<tag::Player>().first() {
+        Some(player) => player.try_get_component::<GameTransform>()?.position,
+        None => return Ok(()),
+    };
+
+    let now = time.elapsed();
+    let mut weapons = manager.query_entities_component_mut::<SpecialWeapon>();
+    let weapon = match weapons.first_mut() {
+        Some((_, weapon)) if weapon.cooldown_left(now) == Duration::ZERO => weapon,
+        _ => return Ok(()),
+    };
+    weapon.last_fired = Some(now);
+    let def = weapon.def.clone();
+
+    let aim = input::aim_target(input, camera, player_pos, def.speed) - player_pos;
+    let projectile = def.projectile();
+    for angle in def.angles(aim.angle()) {
+        let transform = GameTransform::new(player_pos).with_rotation(angle);
+        create_projectile(manager, &projectile, transform);
+    }
+    Ok(())
+}
+
+pub fn lifespan_system(manager: &mut EntityManager, time: &GameTime) -> GameResult<()> {
     let lifespans = manager.query_entities_component_mut::<Lifespan>();
-    let dt = ggez::timer::delta(ctx);
+    let dt = time.delta();
     let mut to_kill_ids = Vec::<EntityId>::with_capacity(lifespans.len());
 
     for (id, life) in lifespans {
@@ -83,69 +157,200 @@ pub fn lifespan_system(manager: &mut EntityManager, ctx: &mut Context) -> GameRe
     Ok(())
 }
 
-pub fn aim_system(manager: &mut EntityManager, ctx: &mut Context) -> GameResult<()> {
-    let entity = manager.get_entities_with_tag::<tag::Player>();
-    let player = entity.first().unwrap();
-    let collider = player.try_get_component::<Collider>()?;
+/// What a `projectile` fired from `origin` along `direction` would run into
+/// first, within the laser's range.
+fn laser_hit(
+    manager: &mut EntityManager,
+    broad_phase: &SpatialGrid<EntityId>,
+    projectile: &ProjectileTemplate,
+    origin: Vec2,
+    direction: Vec2,
+) -> GameResult<Option<(EntityId, RayHit)>> {
+    collision::raycast(
+        manager,
+        broad_phase,
+        origin,
+        direction,
+        LASER_RANGE,
+        projectile.mask,
+    )
+}
+
+pub fn aim_system(
+    manager: &mut EntityManager,
+    broad_phase: &SpatialGrid<EntityId>,
+    input: &ActionState<Action>,
+    camera: &Camera,
+    ctx: &mut Context,
+) -> GameResult<()> {
+    let (collider, projectile) = match manager.get_entities_with_tag::<tag::Player>().first() {
+        Some(player) => (
+            *player.try_get_component::<Collider>()?,
+            player.try_get_component::<Weapon>()?.projectile,
+        ),
+        None => return Ok(()),
+    };
 
-    let mouse_pos: Vec2 = ggez::input::mouse::position(ctx).into();
     let aim_radius = collider.radius * 2f32;
-    let aim_dir = mouse_pos - collider.center;
+    let target = input::aim_target(input, camera, collider.center, aim_radius);
+    let aim_dir = target - collider.center;
 
-    let aim_pos = if aim_dir.magnitude_sq() >= aim_radius.powf(2f32) {
-        collider.center + aim_dir.normalized() * aim_radius
-    } else {
-        mouse_pos
-    };
+    let aim_pos = collider.center + aim_dir.clamp_length(aim_radius);
 
-    let aim_circle = ggez::graphics::MeshBuilder::new()
-        .circle(DrawMode::fill(), aim_pos, 8f32, 0.1, Color::GREEN)?
-        .build(ctx)?;
+    let mut mesh = ggez::graphics::MeshBuilder::new();
+    if aim_dir != Vec2::zero() {
+        let hit = laser_hit(manager, broad_phase, &projectile, collider.center, aim_dir)?;
+        let start = collider.center + aim_dir.normalized() * collider.radius;
+        let end = hit.map_or(
+            collider.center + aim_dir.normalized() * LASER_RANGE,
+            |(_, hit)| hit.point,
+        );
+        if start.distance(end) > 1f32 {
+            mesh.line(&[start, end], 2f32, Color::new(1f32, 0f32, 0f32, 0.5))?;
+        }
+        let on_enemy = hit.is_some_and(|(id, _)| {
+            manager
+                .get_entity(id)
+                .is_some_and(|entity| entity.has_component::<tag::Enemy>())
+        });
+        if on_enemy {
+            mesh.circle(DrawMode::stroke(2f32), end, 12f32, 0.1, Color::RED)?;
+        }
+    }
+    mesh.circle(DrawMode::fill(), aim_pos, 8f32, 0.1, Color::GREEN)?;
+    let aim_mesh = mesh.build(ctx)?;
 
-    ggez::graphics::draw(ctx, &aim_circle, ([0f32, 0f32],))?;
+    ggez::graphics::draw(ctx, &aim_mesh, camera.draw_param(Affine2::IDENTITY))?;
 
     Ok(())
 }
 
-pub fn kill_enemy_system(
-    manager: &mut EntityManager,
-    sender: &mut impl EventSender<EnemyKilled>,
-) -> GameResult<()> {
-    let bullets = manager
-        .get_entities_with_tag::<tag::Bullet>()
-        .into_iter()
-        .filter_map(|b| match b.try_get_component::<Collider>() {
-            Ok(&c) => Some((b.id, c)),
-            _ => None,
-        })
-        .collect::<Vec<(EntityId, Collider)>>();
-
-    let enemies = manager.get_entities_with_tag_mut::<tag::Enemy>();
-    let mut bullet_to_destroy = Vec::<EntityId>::new();
-    let mut sum_score = 0;
-
-    for enemy in enemies {
-        let enemy_collider: BoxCollision = enemy.try_get_component::<Collider>()?.into();
-        if let Some(collide_bullet) = bullets
-            .iter()
-            .find(|b| enemy_collider.collide_aabb(&b.1.into()))
-        {
-            enemy.destroy();
-            bullet_to_destroy.push(collide_bullet.0);
-            sum_score += enemy.try_get_component::<Score>()?.0;
-            let enemy_transform = enemy.try_get_component::<GameTransform>()?.clone();
-            sender.send(EnemyKilled(enemy_transform));
+/// Breaks every killed enemy into one small enemy per corner, flying outwards
+/// evenly spaced around it.
+pub fn split_enemy_system<E>(manager: &mut EntityManager, events: &E) -> GameResult<()>
+where
+    E: EventReceiver<EnemyKilled>,
+{
+    for EnemyKilled(transform, split) in events.peek() {
+        let shape = match split {
+            Some(shape) => *shape,
+            None => continue,
+        };
+        let count = shape.geometry.vertex_count();
+        let step = std::f32::consts::TAU / count as f32;
+        for i in 0..count {
+            let angle = transform.rotation + step * i as f32;
+            let velocity = Vec2::from_angle(angle) * SMALL_ENEMY_SPEED;
+            let piece = GameTransform::new(transform.position).with_rotation(angle);
+            component::create_small_enemy(manager, shape, piece, velocity);
         }
     }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::space_shooter::component::constant::ENEMY_SIZE;
+    use crate::space_shooter::component::general::Score;
+    use crate::space_shooter::component::movement::Speed;
+    use crate::space_shooter::component::physics::layer;
+    use crate::space_shooter::component::shape::{Geometry, Shape};
+    use crate::space_shooter::component::weapon::PLAYER_BULLET;
+    use crate::space_shooter::system::collision::{broad_phase_system, collision_event_system};
+    use crate::space_shooter::system::movement;
+    use common::event::{EventReceiver, EventSender, EventSystem};
+    use common::physics::{CollisionStarted, ContactTracker};
+
+    #[test]
+    fn test_killed_enemy_splits_once_per_corner() {
+        let mut manager = EntityManager::new();
+        let mut events = EventSystem::default();
+        let shape = Shape {
+            geometry: Geometry::Polygon { sides: 5 },
+            radius: 32f32,
+        };
+        events.send(EnemyKilled(
+            GameTransform::new(Vec2::new(10f32, 20f32)),
+            Some(shape),
+        ));
+        events.send(EnemyKilled(GameTransform::new(Vec2::zero()), None));
+        split_enemy_system(&mut manager, &events).unwrap();
+        manager.update();
 
-    for id in bullet_to_destroy {
-        if let Some(entity) = manager.get_entity(id) {
-            entity.destroy();
+        let pieces = manager.get_entities_with_tag::<tag::SmallEnemy>();
+        assert_eq!(pieces.len(), 5);
+        let mut total = Vec2::zero();
+        for piece in &pieces {
+            assert_eq!(piece.get_component::<Shape>().unwrap().radius, 16f32);
+            assert_eq!(piece.get_component::<Score>().unwrap().0, 200);
+            let velocity = piece.get_component::<Speed>().unwrap().velocity;
+            assert!((velocity.magnitude() - SMALL_ENEMY_SPEED).abs() < 1e-3);
+            total += velocity;
         }
+        // Evenly spread around the dead enemy.
+        assert!(total.magnitude() < 1e-3);
     }
 
-    let mut scoreboard = manager.query_entities_component_mut::<Scoreboard>();
-    scoreboard.first_mut().unwrap().1.current_score += sum_score;
+    #[test]
+    fn test_laser_and_bullet_stop_at_same_entity() {
+        let mut manager = EntityManager::new();
+        let mut events = EventSystem::default();
+        let mut grid = SpatialGrid::default();
+        let mut tracker = ContactTracker::default();
+        let mut time = GameTime::default();
 
-    Ok(())
+        // Bullets fly through obstacles, so the laser must not stop there.
+        let obstacle = component::create_obstacle(&mut manager, Vec2::new(150f32, 0f32)).id;
+        let enemy = manager
+            .add_tag(tag::Enemy)
+            .add_component(Collider {
+                center: Vec2::new(400f32, 0f32),
+                radius: ENEMY_SIZE,
+                rotation: 0f32,
+                layer: layer::ENEMY,
+                mask: layer::PLAYER | layer::ENEMY | layer::BULLET | layer::OBSTACLE,
+                trigger: false,
+            })
+            .id;
+        let bullet = create_projectile(
+            &mut manager,
+            &PLAYER_BULLET,
+            GameTransform::new(Vec2::zero()),
+        )
+        .id;
+        manager.update();
+        broad_phase_system(&mut manager, &mut grid).unwrap();
+
+        let laser = laser_hit(
+            &mut manager,
+            &grid,
+            &PLAYER_BULLET,
+            Vec2::zero(),
+            Vec2::new(1f32, 0f32),
+        )
+        .unwrap()
+        .map(|(id, _)| id);
+
+        let mut hit = None;
+        for _ in 0..60 {
+            time.advance(Duration::from_millis(16));
+            movement::integration_system(&mut manager, &time).unwrap();
+            movement::collider_follow_transform_system(&mut manager).unwrap();
+            broad_phase_system(&mut manager, &mut grid).unwrap();
+            collision_event_system(&mut manager, &grid, &mut tracker, &time, &mut events).unwrap();
+            let started: Vec<CollisionStarted> = events.read();
+            for CollisionStarted(collision) in started {
+                if hit.is_none() && collision.a == bullet {
+                    hit = Some(collision.b);
+                } else if hit.is_none() && collision.b == bullet {
+                    hit = Some(collision.a);
+                }
+            }
+        }
+
+        assert_eq!(laser, Some(enemy));
+        assert_eq!(hit, Some(enemy));
+        assert_ne!(hit, Some(obstacle));
+    }
 }
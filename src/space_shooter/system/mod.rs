@@ -1,13 +1,19 @@
+use crate::space_shooter::component::shape::Shape;
 use crate::space_shooter::system::collision::BoundAxis;
 use common::game_transform::GameTransform;
 use ecs::entity::EntityId;
 
+pub mod camera;
 pub mod collision;
+pub mod combat;
 pub mod game;
 pub mod movement;
 pub mod render;
 pub mod ui;
 
-pub struct EnemyKilled(pub GameTransform);
+/// Where an enemy died and the shape it splits into, `None` for small enemies.
+pub struct EnemyKilled(pub GameTransform, pub Option<Shape>);
+
+pub struct PlayerDied(pub GameTransform);
 
 pub struct BoundCollide(pub EntityId, pub BoundAxis);
@@ -1,126 +1,147 @@
 use ecs::manager::EntityManager;
-use ggez::{Context, GameResult};
+use ggez::GameResult;
 use std::time::Duration;
 
-use crate::space_shooter::component::constant::PLAYER_SPEED;
+use crate::space_shooter::component::constant::{
+    BOOST_COOL_DOWN, PLAYER_BOOST_IMPULSE, PLAYER_THRUST,
+};
 use crate::space_shooter::component::game::DisplayTextEvent;
 use crate::space_shooter::component::general::SpeedBoost;
-use crate::space_shooter::component::movement::Speed;
+use crate::space_shooter::component::movement::{RigidBody, Speed};
 use crate::space_shooter::component::physics::Collider;
+use crate::space_shooter::component::weapon::Homing;
+use crate::space_shooter::input::{self, Action};
 use crate::space_shooter::system::collision::BoundAxis;
 use crate::space_shooter::system::BoundCollide;
 use crate::space_shooter::tag;
 use common::event::{EventReceiver, EventSender};
 use common::game_transform::{GameTransform, TryGet};
+use common::input::ActionState;
 use common::math::Vec2;
+use common::time::GameTime;
 
+/// Dashes the player with an impulse, at most once per cool down.
 pub fn player_speed_boost_system(
     manager: &mut EntityManager,
-    ctx: &mut Context,
+    input: &ActionState<Action>,
+    time: &GameTime,
     event_sender: &mut impl EventSender<DisplayTextEvent>,
 ) -> GameResult<()> {
-    let boosts = manager.query_entities_component_mut::<SpeedBoost>();
-    let current_time = ggez::timer::time_since_start(ctx);
-    let tap_boost = ggez::input::keyboard::is_key_pressed(ctx, ggez::event::KeyCode::LShift);
-    let dt = ggez::timer::delta(ctx);
-
-    for (_, boost) in boosts {
-        let should_boost = match boost.last_boost {
-            None => true,
-            Some(last_boost) => current_time - last_boost >= Duration::from_secs(3),
-        };
+    if !input.just_pressed(Action::Boost) {
+        return Ok(());
+    }
+    let current_time = time.elapsed();
 
-        if tap_boost && !boost.is_boosting && should_boost {
-            boost.is_boosting = true;
-            boost.time_left = Duration::from_millis(200);
-            boost.last_boost = Some(current_time);
-        } else if let Some(last_boost) = boost.last_boost {
-            if tap_boost && !should_boost {
+    for player in manager.get_entities_with_tag_mut::<tag::Player>() {
+        let velocity = player.try_get_component::<Speed>()?.velocity;
+        let boost = player.try_get_component_mut::<SpeedBoost>()?;
+        if let Some(last_boost) = boost.last_boost {
+            if current_time - last_boost < BOOST_COOL_DOWN {
                 event_sender.send(DisplayTextEvent {
                     text: format!(
                         "Boosting cool down wait for {} ms.",
                         (current_time - last_boost).as_millis()
                     ),
                     dur: Duration::from_secs(2),
-                })
+                });
+                continue;
             }
         }
+        boost.last_boost = Some(current_time);
 
-        if boost.is_boosting {
-            if boost.time_left <= dt || boost.time_left.is_zero() {
-                boost.is_boosting = false;
-            } else {
-                boost.time_left -= dt;
-            }
-        }
+        let dir = match input::movement_direction(input) {
+            dir if dir != Vec2::zero() => dir,
+            _ => velocity.normalized(),
+        };
+        let body = player.try_get_component_mut::<RigidBody>()?;
+        body.apply_impulse(dir * PLAYER_BOOST_IMPULSE * body.mass);
     }
     Ok(())
 }
 
-pub fn player_movement_system(manager: &mut EntityManager, ctx: &mut Context) -> GameResult<()> {
-    let players = manager.get_entities_with_tag_mut::<tag::Player>();
-    for player in players {
-        let dt = ggez::timer::delta(ctx);
-        let mut dir = Vec2::zero();
-        if ggez::input::keyboard::is_key_pressed(ctx, ggez::event::KeyCode::W) {
-            dir.y += -1f32;
-        }
-        if ggez::input::keyboard::is_key_pressed(ctx, ggez::event::KeyCode::S) {
-            dir.y += 1f32;
-        }
-        if ggez::input::keyboard::is_key_pressed(ctx, ggez::event::KeyCode::A) {
-            dir.x += -1f32;
-        }
-        if ggez::input::keyboard::is_key_pressed(ctx, ggez::event::KeyCode::D) {
-            dir.x += 1f32;
-        }
-        let speed_boost = *player.try_get_component::<SpeedBoost>()?;
-        let transform = player.try_get_component_mut::<GameTransform>()?;
-        let speed = if speed_boost.is_boosting {
-            PLAYER_SPEED * 4f32
-        } else {
-            PLAYER_SPEED
-        };
-
-        transform.position = transform.position + (dir.normalized() * speed * dt.as_secs_f32());
+pub fn player_control_system(
+    manager: &mut EntityManager,
+    input: &ActionState<Action>,
+) -> GameResult<()> {
+    let dir = input::movement_direction(input);
+    for player in manager.get_entities_with_tag_mut::<tag::Player>() {
+        let body = player.try_get_component_mut::<RigidBody>()?;
+        body.apply_force(dir * PLAYER_THRUST * body.mass);
     }
     Ok(())
 }
 
-pub fn enemy_movement_system(
+/// Turns enemies back when they reach the edge of their arena.
+pub fn enemy_bounce_system(
     manager: &mut EntityManager,
     event: &mut impl EventReceiver<BoundCollide>,
-    ctx: &mut Context,
 ) -> GameResult<()> {
-    let enemies = manager.get_entities_with_tag_mut::<tag::Enemy>();
-    let dt = ggez::timer::delta(ctx);
     let collide_events = event.read();
+    for collision in collide_events {
+        let enemy = match manager.get_entity(collision.0) {
+            Some(enemy) if enemy.has_component::<tag::Enemy>() => enemy,
+            _ => continue,
+        };
+        let velocity = &mut enemy.try_get_component_mut::<Speed>()?.velocity;
+        let axis = match collision.1 {
+            BoundAxis::X => Vec2::new(1f32, 0f32),
+            BoundAxis::Y => Vec2::new(0f32, 1f32),
+        };
+        *velocity = velocity.reflect(axis);
+    }
+    Ok(())
+}
 
-    for enemy in enemies {
-        if let Some(collision) = collide_events.iter().find(|e| e.0 == enemy.id) {
-            let velocity = &mut enemy.try_get_component_mut::<Speed>()?.velocity;
-            match collision.1 {
-                BoundAxis::X => velocity.x *= -1f32,
-                BoundAxis::Y => velocity.y *= -1f32,
-            }
-        }
-
-        let speed = enemy.try_get_component::<Speed>()?.velocity;
-        let transform = enemy.try_get_component_mut::<GameTransform>()?;
-        transform.position = transform.position + (speed * dt.as_secs_f32());
+/// Moves every rigid body by its velocity, after applying its forces.
+pub fn integration_system(manager: &mut EntityManager, time: &GameTime) -> GameResult<()> {
+    let dt = time.delta().as_secs_f32();
+    for entity in manager.get_entities_with_tag_mut::<RigidBody>() {
+        let velocity = entity.try_get_component::<Speed>()?.velocity;
+        let body = entity.try_get_component_mut::<RigidBody>()?;
+        let velocity = body.integrate(velocity, dt);
+        let spin = body.angular_velocity * dt;
+        entity.try_get_component_mut::<Speed>()?.velocity = velocity;
+        let transform = entity.try_get_component_mut::<GameTransform>()?;
+        transform.position += velocity * dt;
+        transform.rotation += spin;
     }
     Ok(())
 }
 
-pub fn bullet_movement_system(manager: &mut EntityManager, ctx: &mut Context) -> GameResult<()> {
-    let bullets = manager.get_entities_with_tag_mut::<tag::Bullet>();
-    let dt = ggez::timer::delta(ctx);
-    for bullet in bullets {
-        if let Some(speed) = bullet.get_component::<Speed>() {
-            let speed = speed.velocity;
-            let transform = bullet.try_get_component_mut::<GameTransform>()?;
-            transform.position = transform.position + (speed * dt.as_secs_f32());
-        }
+/// Velocity turned towards `to_target` by at most `max_turn` radians.
+fn steer(velocity: Vec2, to_target: Vec2, max_turn: f32) -> Vec2 {
+    let turn = velocity.angle_to(to_target).clamp(-max_turn, max_turn);
+    velocity.rotated(turn)
+}
+
+/// Turns homing projectiles towards the nearest collider their mask hits.
+pub fn homing_system(manager: &mut EntityManager, time: &GameTime) -> GameResult<()> {
+    let dt = time.delta().as_secs_f32();
+    let targets: Vec<(u32, Vec2)> = manager
+        .query_entities_component::<Collider>()
+        .into_iter()
+        .map(|collider| (collider.layer, collider.center))
+        .collect();
+
+    for entity in manager.get_entities_with_tag_mut::<Homing>() {
+        let turn_rate = entity.try_get_component::<Homing>()?.turn_rate;
+        let collider = *entity.try_get_component::<Collider>()?;
+        let nearest = targets
+            .iter()
+            .filter(|(layer, _)| layer & collider.mask != 0)
+            .map(|&(_, center)| center)
+            .min_by(|a, b| {
+                a.distance(collider.center)
+                    .total_cmp(&b.distance(collider.center))
+            });
+        let target = match nearest {
+            Some(target) => target,
+            None => continue,
+        };
+        let speed = entity.try_get_component_mut::<Speed>()?;
+        speed.velocity = steer(speed.velocity, target - collider.center, turn_rate * dt);
+        let angle = speed.velocity.angle();
+        entity.try_get_component_mut::<GameTransform>()?.rotation = angle;
     }
     Ok(())
 }
@@ -129,9 +150,10 @@ pub fn collider_follow_transform_system(manager: &mut EntityManager) -> GameResu
     let entities = manager.get_all();
     for entity in entities {
         if let Some(transform) = entity.get_component::<GameTransform>() {
-            let updated_pos = transform.position;
+            let (updated_pos, rotation) = (transform.position, transform.rotation);
             if let Some(collider) = entity.get_component_mut::<Collider>() {
                 collider.center = updated_pos;
+                collider.rotation = rotation;
             }
         }
     }
@@ -1,11 +1,21 @@
-use crate::space_shooter::component::game::Scoreboard;
-use crate::space_shooter::component::general::Lifespan;
+use crate::space_shooter::component;
+use crate::space_shooter::component::game::{DisplayText, Lives, Scoreboard};
+use crate::space_shooter::component::general::{HitFlash, Invulnerability, Lifespan};
 use crate::space_shooter::component::shape::{Geometry, Shape};
+use crate::space_shooter::component::weapon::SpecialWeapon;
+use crate::ui::UiNode;
+use common::camera::Camera;
 use common::game_transform::GameTransform;
+use common::math::collision::regular_polygon;
+use common::math::{Affine2, Vec2};
+use common::time::GameTime;
 use ecs::entity::Entity;
 use ecs::manager::EntityManager;
-use ggez::graphics::{Color, DrawMode, Drawable, Font, MeshBuilder, PxScale, Rect, Text};
+use ggez::graphics::{
+    Color, DrawMode, DrawParam, Drawable, Font, MeshBuilder, PxScale, Rect, Text,
+};
 use ggez::{Context, GameResult};
+use std::time::Duration;
 
 fn ease_in(progress: f32) -> f32 {
     if progress == 0f32 {
@@ -25,9 +35,10 @@ fn lifespan_color(lifespan: Option<&Lifespan>, mut color: Color) -> Color {
     color
 }
 
+/// Mesh of the shape around the origin, placed by the entity's transform
+/// when drawn.
 fn get_drawable(
     shape: &Shape,
-    transform: &GameTransform,
     ctx: &mut Context,
     draw_mode: DrawMode,
     color: Color,
@@ -37,50 +48,174 @@ fn get_drawable(
         Geometry::Rectangle => mesh_builder.rectangle(
             draw_mode,
             Rect::new(
-                transform.position.x - shape.radius,
-                transform.position.y - shape.radius,
+                -shape.radius,
+                -shape.radius,
                 shape.radius * 2f32,
                 shape.radius * 2f32,
             ),
             color,
         ),
-        Geometry::Circle => {
-            mesh_builder.circle(draw_mode, transform.position, shape.radius, 0.1, color)
-        }
+        Geometry::Circle => mesh_builder.circle(draw_mode, Vec2::zero(), shape.radius, 0.1, color),
+        Geometry::Polygon { sides } => mesh_builder.polygon(
+            draw_mode,
+            &regular_polygon(Vec2::zero(), shape.radius, sides, 0f32),
+            color,
+        ),
     }?
     .build(ctx)
 }
 
-fn render_shapes(entities: &[&mut Entity], ctx: &mut Context) -> GameResult<()> {
+fn render_shapes(entities: &[&mut Entity], camera: &Camera, ctx: &mut Context) -> GameResult<()> {
     for entity in entities {
         if let (Some(shape), Some(transform)) = (
             entity.get_component::<Shape>(),
             entity.get_component::<GameTransform>(),
         ) {
             let lifespan = entity.get_component::<Lifespan>();
-            let shape_color = lifespan_color(lifespan, Color::BLACK);
-            let border_color = lifespan_color(lifespan, Color::RED);
+            let flashing = entity
+                .get_component::<HitFlash>()
+                .is_some_and(|flash| flash.time_left > Duration::ZERO);
+            let fill = if flashing { Color::WHITE } else { Color::BLACK };
+            let mut shape_color = lifespan_color(lifespan, fill);
+            let mut border_color = lifespan_color(lifespan, Color::RED);
+            if let Some(invulnerability) = entity.get_component::<Invulnerability>() {
+                // Blinks every 100 ms while hits are ignored.
+                if invulnerability.active() && invulnerability.time_left.as_millis() / 100 % 2 == 0
+                {
+                    shape_color.a *= 0.3;
+                    border_color.a *= 0.3;
+                }
+            }
 
-            let shape_draw = get_drawable(shape, transform, ctx, DrawMode::fill(), shape_color)?;
-            let border = get_drawable(shape, transform, ctx, DrawMode::stroke(3f32), border_color)?;
+            let shape_draw = get_drawable(shape, ctx, DrawMode::fill(), shape_color)?;
+            let border = get_drawable(shape, ctx, DrawMode::stroke(3f32), border_color)?;
 
-            ggez::graphics::draw(ctx, &shape_draw, ([0f32, 0f32],))?;
-            ggez::graphics::draw(ctx, &border, ([0f32, 0f32],))?;
+            let param = camera.draw_param(transform.matrix());
+            ggez::graphics::draw(ctx, &shape_draw, param)?;
+            ggez::graphics::draw(ctx, &border, param)?;
         }
     }
     Ok(())
 }
 
-pub fn render_shape_system(manager: &mut EntityManager, ctx: &mut Context) -> GameResult<()> {
-    render_shapes(&manager.get_all(), ctx)
+pub fn render_shape_system(
+    manager: &mut EntityManager,
+    camera: &Camera,
+    ctx: &mut Context,
+) -> GameResult<()> {
+    render_shapes(&manager.get_all(), camera, ctx)
+}
+
+pub fn render_arena_system(
+    manager: &mut EntityManager,
+    camera: &Camera,
+    ctx: &mut Context,
+) -> GameResult<()> {
+    for (_, bounds) in component::arenas(manager) {
+        let border = MeshBuilder::new()
+            .rectangle(DrawMode::stroke(4f32), bounds, Color::BLACK)?
+            .build(ctx)?;
+        ggez::graphics::draw(ctx, &border, camera.draw_param(Affine2::IDENTITY))?;
+    }
+    Ok(())
+}
+
+const BAR_WIDTH: f32 = 160f32;
+const BAR_HEIGHT: f32 = 8f32;
+
+fn hud_text(text: String, size: f32) -> Text {
+    let mut text = Text::new(text);
+    text.set_font(Font::default(), PxScale::from(size));
+    text
+}
+
+fn scoreboard_text(board: &Scoreboard) -> Text {
+    hud_text(format!("Score: {}", board.current_score), 32f32)
+}
+
+fn lives_text(lives: &Lives) -> Text {
+    hud_text(format!("Lives: {}", lives.left), 32f32)
+}
+
+fn special_weapon_text(weapon: &SpecialWeapon, time: &GameTime) -> Text {
+    let left = weapon.cooldown_left(time.elapsed());
+    let label = if left == Duration::ZERO {
+        "Special: ready".to_string()
+    } else {
+        format!("Special: {:.1}s", left.as_secs_f32())
+    };
+    hud_text(label, 24f32)
+}
+
+/// Size of a HUD entity for the layout pass, `None` for anything else.
+pub fn measure_hud(entity: &Entity, time: &GameTime, ctx: &Context) -> Option<(f32, f32)> {
+    let size = |text: &Text| (text.width(ctx), text.height(ctx));
+    if let Some(board) = entity.get_component::<Scoreboard>() {
+        return Some(size(&scoreboard_text(board)));
+    }
+    if let Some(lives) = entity.get_component::<Lives>() {
+        return Some(size(&lives_text(lives)));
+    }
+    if let Some(weapon) = entity.get_component::<SpecialWeapon>() {
+        let (w, h) = size(&special_weapon_text(weapon, time));
+        return Some((w.max(BAR_WIDTH), h + BAR_HEIGHT * 2f32));
+    }
+    if let Some(cache) = entity
+        .get_component::<DisplayText>()
+        .and_then(|display_text| display_text.cache.as_ref())
+    {
+        return Some(size(&cache.text));
+    }
+    None
+}
+
+/// Cooldown of the special weapon, with a bar that fills up as it recharges.
+pub fn render_special_weapon_system(
+    manager: &EntityManager,
+    time: &GameTime,
+    ctx: &mut Context,
+) -> GameResult<()> {
+    let weapons = manager.query_entities_components::<(SpecialWeapon, UiNode)>();
+    for (weapon, node) in weapons {
+        let rect = node.rect;
+        let text = special_weapon_text(weapon, time);
+        ggez::graphics::draw(ctx, &text, ([rect.x, rect.y], Color::BLACK))?;
+
+        let left = weapon.cooldown_left(time.elapsed());
+        let cooldown = weapon.def.cooldown.as_secs_f32();
+        let charged = if cooldown > 0f32 {
+            1f32 - left.as_secs_f32() / cooldown
+        } else {
+            1f32
+        };
+        let bar = Rect::new(rect.x, rect.bottom() - BAR_HEIGHT, BAR_WIDTH, BAR_HEIGHT);
+        let mut mesh = MeshBuilder::new();
+        if charged > 0f32 {
+            let fill = Rect::new(bar.x, bar.y, bar.w * charged, bar.h);
+            mesh.rectangle(DrawMode::fill(), fill, Color::GREEN)?;
+        }
+        mesh.rectangle(DrawMode::stroke(1f32), bar, Color::BLACK)?;
+        let mesh = mesh.build(ctx)?;
+        ggez::graphics::draw(ctx, &mesh, DrawParam::new())?;
+    }
+
+    Ok(())
+}
+
+pub fn render_lives_system(manager: &EntityManager, ctx: &mut Context) -> GameResult<()> {
+    for (lives, node) in manager.query_entities_components::<(Lives, UiNode)>() {
+        let text = lives_text(lives);
+        ggez::graphics::draw(ctx, &text, ([node.rect.x, node.rect.y], Color::BLACK))?;
+    }
+
+    Ok(())
 }
 
 pub fn render_scoreboard_system(manager: &EntityManager, ctx: &mut Context) -> GameResult<()> {
-    let boards = manager.query_entities_component::<Scoreboard>();
-    for board in boards {
-        let mut text = Text::new(format!("Score: {}", board.current_score));
-        text.set_font(Font::default(), PxScale::from(32f32));
-        ggez::graphics::draw(ctx, &text, ([12f32, 12f32], Color::BLACK))?;
+    let boards = manager.query_entities_components::<(Scoreboard, UiNode)>();
+    for (board, node) in boards {
+        let text = scoreboard_text(board);
+        ggez::graphics::draw(ctx, &text, ([node.rect.x, node.rect.y], Color::BLACK))?;
     }
 
     Ok(())
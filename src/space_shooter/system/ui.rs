@@ -1,7 +1,8 @@
 use crate::space_shooter::component::game::{CacheDisplayText, DisplayText, DisplayTextEvent};
 use crate::space_shooter::tag;
-use crate::{WINDOWS_HEIGHT, WINDOWS_WIDTH};
+use crate::ui::UiNode;
 use common::event::EventReceiver;
+use common::time::GameTime;
 use ecs::manager::EntityManager;
 use ggez::graphics::{Color, Font, PxScale};
 use ggez::{Context, GameResult};
@@ -10,7 +11,7 @@ use std::ops::Add;
 pub fn lifetime_debug_text_system(
     event_reader: &mut impl EventReceiver<DisplayTextEvent>,
     manager: &mut EntityManager,
-    ctx: &mut Context,
+    time: &GameTime,
 ) {
     let mut display_text = manager.query_entities_component_tag_mut::<DisplayText, tag::Ui>();
     if display_text.is_empty() {
@@ -21,7 +22,7 @@ pub fn lifetime_debug_text_system(
         display_text.texts.push(event);
     }
 
-    let dt = ggez::timer::delta(ctx);
+    let dt = time.delta();
     display_text.texts = display_text
         .texts
         .iter()
@@ -37,12 +38,14 @@ pub fn lifetime_debug_text_system(
         .collect();
 }
 
-pub fn display_debug_text_system(manager: &mut EntityManager, ctx: &mut Context) -> GameResult<()> {
+/// Rebuilds the cached debug text when the texts changed, before the layout
+/// pass measures it.
+pub fn cache_debug_text_system(manager: &mut EntityManager) {
     let mut display_text = manager.query_entities_component_tag_mut::<DisplayText, tag::Ui>();
-    if display_text.is_empty() {
-        return Ok(());
-    }
-    let display_text = &mut display_text[0];
+    let display_text = match display_text.first_mut() {
+        Some(display_text) => display_text,
+        None => return,
+    };
     let raw_text = display_text.texts.iter().fold(String::new(), |s, t| {
         let new_str = s.add("\n");
         new_str.add(&t.text)
@@ -56,14 +59,15 @@ pub fn display_debug_text_system(manager: &mut EntityManager, ctx: &mut Context)
     if should_update_cache {
         let mut text = ggez::graphics::Text::new(raw_text.clone());
         text.set_font(Font::default(), PxScale::from(15f32));
-        let (w, h) = (text.width(ctx), text.height(ctx));
-        let position = [WINDOWS_WIDTH / 2f32 - w / 2f32, WINDOWS_HEIGHT - 32f32 - h];
-        display_text.cache = Some(CacheDisplayText {
-            raw_text,
-            text,
-            position,
-        })
+        display_text.cache = Some(CacheDisplayText { raw_text, text })
+    }
+}
+
+pub fn display_debug_text_system(manager: &EntityManager, ctx: &mut Context) -> GameResult<()> {
+    for (display_text, node) in manager.query_entities_components::<(DisplayText, UiNode)>() {
+        if let Some(cache) = &display_text.cache {
+            ggez::graphics::draw(ctx, &cache.text, ([node.rect.x, node.rect.y], Color::BLACK))?;
+        }
     }
-    let cache = display_text.cache.as_ref().unwrap();
-    ggez::graphics::draw(ctx, &cache.text, (cache.position, Color::BLACK))
+    Ok(())
 }
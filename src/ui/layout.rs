@@ -0,0 +1,295 @@
+use std::collections::HashMap;
+
+use common::anchor::{anchored, Anchor, Margin};
+use ecs::entity::{Entity, EntityId};
+use ecs::manager::EntityManager;
+use ggez::graphics::Rect;
+use ggez::{Context, GameResult};
+
+use crate::space_shooter::tag;
+use crate::ui::{ui_area, widget};
+
+/// Places a ui entity inside its parent, or inside the screen when it has
+/// none. `rect` is written by `ui_layout_system`.
+#[derive(Clone, Debug, Default)]
+pub struct UiNode {
+    pub anchor: Anchor,
+    pub margin: Margin,
+    pub parent: Option<EntityId>,
+    pub rect: Rect,
+}
+
+impl UiNode {
+    pub fn new(anchor: Anchor) -> Self {
+        Self {
+            anchor,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_margin(mut self, margin: Margin) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    pub fn with_parent(mut self, parent: EntityId) -> Self {
+        self.parent = Some(parent);
+        self
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Vertical,
+    Horizontal,
+}
+
+/// Lines up the children of a node one after another, in creation order.
+/// Each child is aligned on the other axis by its own anchor.
+#[derive(Clone, Copy, Debug)]
+pub struct Stack {
+    pub direction: Direction,
+    pub spacing: f32,
+}
+
+struct LayoutItem {
+    id: EntityId,
+    node: UiNode,
+    content: (f32, f32),
+    stack: Option<Stack>,
+}
+
+struct Layout<'i> {
+    items: &'i [LayoutItem],
+    sizes: HashMap<EntityId, (f32, f32)>,
+    rects: HashMap<EntityId, Rect>,
+}
+
+impl<'i> Layout<'i> {
+    fn children(&self, parent: EntityId) -> impl Iterator<Item = &'i LayoutItem> {
+        self.items
+            .iter()
+            .filter(move |item| item.node.parent == Some(parent))
+    }
+
+    /// Stacks grow to fit their children, everything else keeps its content size.
+    fn size(&mut self, item: &LayoutItem) -> (f32, f32) {
+        if let Some(size) = self.sizes.get(&item.id) {
+            return *size;
+        }
+        let size = match item.stack {
+            None => item.content,
+            Some(stack) => {
+                let children: Vec<&LayoutItem> = self.children(item.id).collect();
+                let (mut main, mut cross) = (0f32, 0f32);
+                for child in &children {
+                    let (w, h) = self.size(child);
+                    let margin = child.node.margin;
+                    let (w, h) = (
+                        w + margin.left + margin.right,
+                        h + margin.top + margin.bottom,
+                    );
+                    match stack.direction {
+                        Direction::Vertical => {
+                            main += h;
+                            cross = cross.max(w);
+                        }
+                        Direction::Horizontal => {
+                            main += w;
+                            cross = cross.max(h);
+                        }
+                    }
+                }
+                main += stack.spacing * children.len().saturating_sub(1) as f32;
+                match stack.direction {
+                    Direction::Vertical => (cross.max(item.content.0), main),
+                    Direction::Horizontal => (main, cross.max(item.content.1)),
+                }
+            }
+        };
+        self.sizes.insert(item.id, size);
+        size
+    }
+
+    fn place(&mut self, item: &LayoutItem, container: Rect) {
+        let rect = anchored(
+            item.node.anchor,
+            item.node.margin,
+            self.size(item),
+            container,
+        );
+        self.rects.insert(item.id, rect);
+
+        let children: Vec<&LayoutItem> = self.children(item.id).collect();
+        let mut cursor = 0f32;
+        for child in children {
+            let slot = match item.stack {
+                None => rect,
+                Some(stack) => {
+                    let (w, h) = self.size(child);
+                    let margin = child.node.margin;
+                    let slot = match stack.direction {
+                        Direction::Vertical => {
+                            let h = h + margin.top + margin.bottom;
+                            Rect::new(rect.x, rect.y + cursor, rect.w, h)
+                        }
+                        Direction::Horizontal => {
+                            let w = w + margin.left + margin.right;
+                            Rect::new(rect.x + cursor, rect.y, w, rect.h)
+                        }
+                    };
+                    cursor += match stack.direction {
+                        Direction::Vertical => slot.h,
+                        Direction::Horizontal => slot.w,
+                    } + stack.spacing;
+                    slot
+                }
+            };
+            self.place(child, slot);
+        }
+    }
+}
+
+/// Resolves the rect of every item. Items whose parent is missing are
+/// placed on the screen.
+fn resolve(items: &[LayoutItem], screen: Rect) -> HashMap<EntityId, Rect> {
+    let mut layout = Layout {
+        items,
+        sizes: HashMap::new(),
+        rects: HashMap::new(),
+    };
+    let roots = items.iter().filter(|item| {
+        item.node
+            .parent
+            .is_none_or(|parent| items.iter().all(|other| other.id != parent))
+    });
+    for root in roots {
+        layout.place(root, screen);
+    }
+    layout.rects
+}
+
+/// Resolves the `UiNode` rect of every ui entity. Widgets measure themselves,
+/// `measure` gives the size of anything else drawn at its node.
+pub fn ui_layout_system<M>(
+    manager: &mut EntityManager,
+    ctx: &mut Context,
+    measure: M,
+) -> GameResult<()>
+where
+    M: Fn(&Entity, &Context) -> Option<(f32, f32)>,
+{
+    let mut nodes = manager.get_entities_with_tag::<tag::Ui>();
+    nodes.retain(|entity| entity.has_component::<UiNode>());
+    nodes.sort_by_key(|entity| entity.id);
+
+    let items: Vec<LayoutItem> = nodes
+        .into_iter()
+        .map(|entity| LayoutItem {
+            id: entity.id,
+            node: entity.get_component::<UiNode>().unwrap().clone(),
+            content: widget::measure(entity, ctx)
+                .or_else(|| measure(entity, ctx))
+                .unwrap_or_default(),
+            stack: entity.get_component::<Stack>().copied(),
+        })
+        .collect();
+
+    for (id, rect) in resolve(&items, ui_area()) {
+        if let Some(node) = manager
+            .get_entity(id)
+            .and_then(|entity| entity.get_component_mut::<UiNode>())
+        {
+            node.rect = rect;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use ecs::entity::EntityId;
+    use ggez::graphics::Rect;
+
+    use common::anchor::{Anchor, Margin};
+
+    use super::{resolve, Direction, LayoutItem, Stack, UiNode};
+
+    const SCREEN: Rect = Rect::new(0f32, 0f32, 800f32, 600f32);
+
+    fn item(id: EntityId, node: UiNode, content: (f32, f32)) -> LayoutItem {
+        LayoutItem {
+            id,
+            node,
+            content,
+            stack: None,
+        }
+    }
+
+    fn stack(id: EntityId, node: UiNode, direction: Direction) -> LayoutItem {
+        LayoutItem {
+            id,
+            node,
+            content: (0f32, 0f32),
+            stack: Some(Stack {
+                direction,
+                spacing: 10f32,
+            }),
+        }
+    }
+
+    #[test]
+    fn test_vertical_stack() {
+        let items = [
+            stack(1, UiNode::new(Anchor::Center), Direction::Vertical),
+            item(
+                2,
+                UiNode::new(Anchor::Center).with_parent(1),
+                (200f32, 40f32),
+            ),
+            item(3, UiNode::new(Anchor::Left).with_parent(1), (100f32, 60f32)),
+        ];
+
+        let rects = resolve(&items, SCREEN);
+
+        assert_eq!(rects[&1], Rect::new(300f32, 245f32, 200f32, 110f32));
+        assert_eq!(rects[&2], Rect::new(300f32, 245f32, 200f32, 40f32));
+        assert_eq!(rects[&3], Rect::new(300f32, 295f32, 100f32, 60f32));
+    }
+
+    #[test]
+    fn test_horizontal_stack_with_margins() {
+        let margin = Margin {
+            left: 5f32,
+            ..Default::default()
+        };
+        let items = [
+            stack(1, UiNode::new(Anchor::TopLeft), Direction::Horizontal),
+            item(2, UiNode::new(Anchor::Top).with_parent(1), (50f32, 20f32)),
+            item(
+                3,
+                UiNode::new(Anchor::Bottom)
+                    .with_parent(1)
+                    .with_margin(margin),
+                (50f32, 40f32),
+            ),
+        ];
+
+        let rects = resolve(&items, SCREEN);
+
+        assert_eq!(rects[&1], Rect::new(0f32, 0f32, 115f32, 40f32));
+        assert_eq!(rects[&2], Rect::new(0f32, 0f32, 50f32, 20f32));
+        assert_eq!(rects[&3], Rect::new(65f32, 0f32, 50f32, 40f32));
+    }
+
+    #[test]
+    fn test_follows_screen_size() {
+        let items = [item(1, UiNode::new(Anchor::BottomRight), (10f32, 10f32))];
+
+        let small = resolve(&items, SCREEN);
+        let large = resolve(&items, Rect::new(0f32, 0f32, 1600f32, 900f32));
+
+        assert_eq!(small[&1], Rect::new(790f32, 590f32, 10f32, 10f32));
+        assert_eq!(large[&1], Rect::new(1590f32, 890f32, 10f32, 10f32));
+    }
+}
@@ -0,0 +1,532 @@
+use common::event::EventSender;
+use common::math::Vec2;
+use ecs::entity::EntityId;
+use ecs::manager::EntityManager;
+use ggez::event::{Button as PadButton, KeyCode, KeyMods, MouseButton};
+use ggez::{
+    graphics::{Color, Font, PxScale, Rect, Text},
+    Context, GameResult,
+};
+
+use crate::space_shooter::tag;
+use crate::{LOGICAL_HEIGHT, LOGICAL_WIDTH};
+
+pub use common::anchor::{anchored, Anchor, Margin};
+pub use layout::{ui_layout_system, Direction, Stack, UiNode};
+pub use widget::{Button, Checkbox, Label, Slider, TextInput, Widget};
+
+mod layout;
+mod widget;
+
+/// Sent by `ui_interaction_system` when a widget is used.
+#[derive(Clone, Debug, PartialEq)]
+pub enum UiEvent {
+    Clicked(EntityId),
+    Toggled(EntityId, bool),
+    ValueChanged(EntityId, f32),
+    TextChanged(EntityId, String),
+    Submitted(EntityId, String),
+}
+
+/// Raw input translated into what it means for the widgets.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum UiInput {
+    PointerMoved(Vec2),
+    PointerDown(Vec2),
+    PointerUp(Vec2),
+    FocusNext,
+    FocusPrevious,
+    Activate,
+    Submit,
+    Adjust(f32),
+    Char(char),
+    DeleteBack,
+}
+
+/// Pointer, focus and queued input of one scene's widgets. Scenes hand it
+/// their ggez events and `ui_interaction_system` applies them on update.
+#[derive(Debug, Default)]
+pub struct Ui {
+    inputs: Vec<UiInput>,
+    pointer: Option<Vec2>,
+    focus: Option<EntityId>,
+    pressed: Option<EntityId>,
+}
+
+impl Ui {
+    pub fn set_focus(&mut self, focus: Option<EntityId>) {
+        self.focus = focus;
+    }
+
+    pub fn mouse_motion(&mut self, x: f32, y: f32) {
+        self.inputs.push(UiInput::PointerMoved(Vec2::new(x, y)));
+    }
+
+    pub fn mouse_button_down(&mut self, button: MouseButton, x: f32, y: f32) {
+        if button == MouseButton::Left {
+            self.inputs.push(UiInput::PointerDown(Vec2::new(x, y)));
+        }
+    }
+
+    pub fn mouse_button_up(&mut self, button: MouseButton, x: f32, y: f32) {
+        if button == MouseButton::Left {
+            self.inputs.push(UiInput::PointerUp(Vec2::new(x, y)));
+        }
+    }
+
+    pub fn key_down(&mut self, keycode: KeyCode, keymods: KeyMods, repeat: bool) {
+        let input = match keycode {
+            KeyCode::Tab if keymods.contains(KeyMods::SHIFT) => UiInput::FocusPrevious,
+            KeyCode::Tab | KeyCode::Down => UiInput::FocusNext,
+            KeyCode::Up => UiInput::FocusPrevious,
+            KeyCode::Left => UiInput::Adjust(-1f32),
+            KeyCode::Right => UiInput::Adjust(1f32),
+            KeyCode::Space if !repeat => UiInput::Activate,
+            KeyCode::Return if !repeat => UiInput::Submit,
+            KeyCode::Back => UiInput::DeleteBack,
+            _ => return,
+        };
+        self.inputs.push(input);
+    }
+
+    pub fn gamepad_button_down(&mut self, btn: PadButton) {
+        let input = match btn {
+            PadButton::DPadDown => UiInput::FocusNext,
+            PadButton::DPadUp => UiInput::FocusPrevious,
+            PadButton::DPadLeft => UiInput::Adjust(-1f32),
+            PadButton::DPadRight => UiInput::Adjust(1f32),
+            PadButton::South => UiInput::Submit,
+            _ => return,
+        };
+        self.inputs.push(input);
+    }
+
+    pub fn text_input(&mut self, character: char) {
+        if !character.is_control() {
+            self.inputs.push(UiInput::Char(character));
+        }
+    }
+
+    fn handle_inputs(
+        &mut self,
+        manager: &mut EntityManager,
+        layout: &[(EntityId, Rect)],
+        sender: &mut impl EventSender<UiEvent>,
+    ) {
+        let enabled: Vec<(EntityId, Rect)> = layout
+            .iter()
+            .filter(|(id, _)| {
+                manager.get_entity(*id).is_some_and(|entity| {
+                    widget::is_interactive(entity)
+                        && entity
+                            .get_component::<Widget>()
+                            .is_some_and(|w| !w.disabled)
+                })
+            })
+            .copied()
+            .collect();
+        let is_enabled = |id: Option<EntityId>| enabled.iter().any(|(e, _)| Some(*e) == id);
+        let under = |point: Vec2| {
+            let position: [f32; 2] = point.into();
+            enabled
+                .iter()
+                .find(|(_, rect)| rect.contains(position))
+                .map(|(id, _)| *id)
+        };
+
+        if !is_enabled(self.focus) {
+            self.focus = None;
+        }
+        if !is_enabled(self.pressed) {
+            self.pressed = None;
+        }
+
+        for input in std::mem::take(&mut self.inputs) {
+            match input {
+                UiInput::PointerMoved(point) => {
+                    self.pointer = Some(point);
+                    if let Some((id, rect)) =
+                        enabled.iter().find(|(id, _)| Some(*id) == self.pressed)
+                    {
+                        drag_slider(manager, *id, *rect, point.x, sender);
+                    }
+                }
+                UiInput::PointerDown(point) => {
+                    self.pointer = Some(point);
+                    self.pressed = under(point);
+                    self.focus = self.pressed;
+                    if let Some((id, rect)) =
+                        enabled.iter().find(|(id, _)| Some(*id) == self.pressed)
+                    {
+                        drag_slider(manager, *id, *rect, point.x, sender);
+                    }
+                }
+                UiInput::PointerUp(point) => {
+                    self.pointer = Some(point);
+                    if let Some(id) = self.pressed.take() {
+                        if under(point) == Some(id) {
+                            activate(manager, id, sender);
+                        }
+                    }
+                }
+                UiInput::FocusNext | UiInput::FocusPrevious if !enabled.is_empty() => {
+                    let len = enabled.len();
+                    let current = enabled.iter().position(|(id, _)| Some(*id) == self.focus);
+                    let next = match (current, input) {
+                        (None, UiInput::FocusNext) => 0,
+                        (None, _) => len - 1,
+                        (Some(idx), UiInput::FocusNext) => (idx + 1) % len,
+                        (Some(idx), _) => (idx + len - 1) % len,
+                    };
+                    self.focus = Some(enabled[next].0);
+                }
+                UiInput::FocusNext | UiInput::FocusPrevious => {}
+                UiInput::Activate => {
+                    if let Some(id) = self.focus {
+                        if !has::<TextInput>(manager, id) {
+                            activate(manager, id, sender);
+                        }
+                    }
+                }
+                UiInput::Submit => {
+                    if let Some(id) = self.focus {
+                        activate(manager, id, sender);
+                    }
+                }
+                UiInput::Adjust(direction) => {
+                    if let Some(slider) = self.focused_mut::<Slider>(manager) {
+                        let value = slider.value + direction * slider.step;
+                        if slider.set_value(value) {
+                            let value = slider.value;
+                            sender.send(UiEvent::ValueChanged(self.focus.unwrap(), value));
+                        }
+                    }
+                }
+                UiInput::Char(character) => {
+                    if let Some(input) = self.focused_mut::<TextInput>(manager) {
+                        if input.text.chars().count() < input.max_len {
+                            input.text.push(character);
+                            let text = input.text.clone();
+                            sender.send(UiEvent::TextChanged(self.focus.unwrap(), text));
+                        }
+                    }
+                }
+                UiInput::DeleteBack => {
+                    if let Some(input) = self.focused_mut::<TextInput>(manager) {
+                        if input.text.pop().is_some() {
+                            let text = input.text.clone();
+                            sender.send(UiEvent::TextChanged(self.focus.unwrap(), text));
+                        }
+                    }
+                }
+            }
+        }
+
+        let hovered = self.pointer.and_then(under);
+        for (id, _) in layout {
+            if let Some(widget) = manager
+                .get_entity(*id)
+                .and_then(|entity| entity.get_component_mut::<Widget>())
+            {
+                widget.hovered = hovered == Some(*id);
+                widget.pressed = self.pressed == Some(*id);
+                widget.focused = self.focus == Some(*id);
+            }
+        }
+    }
+
+    fn focused_mut<'m, T: 'static>(&self, manager: &'m mut EntityManager) -> Option<&'m mut T> {
+        manager
+            .get_entity(self.focus?)
+            .and_then(|entity| entity.get_component_mut::<T>())
+    }
+}
+
+fn has<T: 'static>(manager: &mut EntityManager, id: EntityId) -> bool {
+    manager
+        .get_entity(id)
+        .is_some_and(|entity| entity.has_component::<T>())
+}
+
+fn activate(manager: &mut EntityManager, id: EntityId, sender: &mut impl EventSender<UiEvent>) {
+    let entity = match manager.get_entity(id) {
+        Some(entity) => entity,
+        None => return,
+    };
+    if entity.has_component::<Button>() {
+        sender.send(UiEvent::Clicked(id));
+    } else if let Some(checkbox) = entity.get_component_mut::<Checkbox>() {
+        checkbox.checked = !checkbox.checked;
+        sender.send(UiEvent::Toggled(id, checkbox.checked));
+    } else if let Some(input) = entity.get_component::<TextInput>() {
+        sender.send(UiEvent::Submitted(id, input.text.clone()));
+    }
+}
+
+fn drag_slider(
+    manager: &mut EntityManager,
+    id: EntityId,
+    rect: Rect,
+    x: f32,
+    sender: &mut impl EventSender<UiEvent>,
+) {
+    if let Some(slider) = manager
+        .get_entity(id)
+        .and_then(|entity| entity.get_component_mut::<Slider>())
+    {
+        let value = slider.value_at(rect, x);
+        if slider.set_value(value) {
+            sender.send(UiEvent::ValueChanged(id, slider.value));
+        }
+    }
+}
+
+/// Logical area the ui is anchored in. The viewport scales it to the window,
+/// so positions inside it never change when the window is resized.
+pub fn ui_area() -> Rect {
+    Rect::new(0f32, 0f32, LOGICAL_WIDTH, LOGICAL_HEIGHT)
+}
+
+/// Adds a widget of `kind` laid out inside `parent`.
+pub(crate) fn add_widget<T: 'static>(
+    manager: &mut EntityManager,
+    parent: EntityId,
+    kind: T,
+) -> EntityId {
+    manager
+        .add_tag(tag::Ui)
+        .add_component(kind)
+        .add_component(Widget::default())
+        .add_component(UiNode::new(Anchor::Center).with_parent(parent))
+        .id
+}
+
+/// Runs the layout pass and returns the rect of every widget in creation order.
+pub fn widget_layout(
+    manager: &mut EntityManager,
+    ctx: &mut Context,
+) -> GameResult<Vec<(EntityId, Rect)>> {
+    ui_layout_system(manager, ctx, |_, _| None)?;
+
+    let mut widgets = manager.get_entities_with_tag::<tag::Ui>();
+    widgets.retain(|entity| entity.has_component::<Widget>());
+    widgets.sort_by_key(|entity| entity.id);
+    Ok(widgets
+        .into_iter()
+        .filter_map(|entity| Some((entity.id, entity.get_component::<UiNode>()?.rect)))
+        .collect())
+}
+
+/// Applies the input queued on `ui` to the widgets and sends a `UiEvent` for
+/// every click, toggle and edit.
+pub fn ui_interaction_system(
+    manager: &mut EntityManager,
+    ui: &mut Ui,
+    ctx: &mut Context,
+    event_sender: &mut impl EventSender<UiEvent>,
+) -> GameResult<()> {
+    let layout = widget_layout(manager, ctx)?;
+    ui.handle_inputs(manager, &layout, event_sender);
+    Ok(())
+}
+
+pub fn render_ui_system(manager: &mut EntityManager, ctx: &mut Context) -> GameResult<()> {
+    for (id, rect) in widget_layout(manager, ctx)? {
+        if let Some(entity) = manager.get_entity(id) {
+            widget::draw(entity, rect, ctx)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn render_fps_system(ctx: &mut Context) -> GameResult<()> {
+    let dt = ggez::timer::delta(ctx);
+    let fps = ggez::timer::fps(ctx);
+
+    let mut text = Text::new(format!("fps: {}; delta: {}", fps.round(), dt.as_millis()));
+    text.set_font(Font::default(), PxScale::from(15f32));
+    let size = (text.width(ctx), text.height(ctx));
+    let rect = anchored(Anchor::BottomRight, Margin::all(10f32), size, ui_area());
+    ggez::graphics::draw(ctx, &text, ([rect.x, rect.y], Color::BLACK))
+}
+
+#[cfg(test)]
+mod tests {
+    use common::event::{EventReceiver, EventSystem};
+    use ecs::entity::EntityId;
+    use ecs::manager::EntityManager;
+    use ggez::event::{KeyCode, KeyMods, MouseButton};
+    use ggez::graphics::Rect;
+
+    use super::{add_widget, Button, Checkbox, TextInput, Ui, UiEvent, Widget};
+
+    /// Interaction only looks at the layout it is handed, so the widgets
+    /// can hang off a parent that was never added.
+    const MENU: EntityId = 0;
+
+    fn button(title: &str) -> Button {
+        Button {
+            title: title.to_string(),
+            size: 20f32,
+        }
+    }
+
+    fn run(ui: &mut Ui, manager: &mut EntityManager, layout: &[(EntityId, Rect)]) -> Vec<UiEvent> {
+        let mut events = EventSystem::default();
+        ui.handle_inputs(manager, layout, &mut events);
+        events.read()
+    }
+
+    #[test]
+    fn test_click_needs_press_and_release_inside() {
+        let mut manager = EntityManager::new();
+        let id = add_widget(&mut manager, MENU, button("Play"));
+        manager.update();
+        let layout = [(id, Rect::new(0f32, 0f32, 100f32, 40f32))];
+        let mut ui = Ui::default();
+
+        ui.mouse_button_down(MouseButton::Left, 10f32, 10f32);
+        assert!(run(&mut ui, &mut manager, &layout).is_empty());
+        assert!(
+            manager
+                .get_entity(id)
+                .unwrap()
+                .get_component::<Widget>()
+                .unwrap()
+                .pressed
+        );
+
+        ui.mouse_button_up(MouseButton::Left, 20f32, 20f32);
+        assert_eq!(
+            run(&mut ui, &mut manager, &layout),
+            vec![UiEvent::Clicked(id)]
+        );
+
+        ui.mouse_button_down(MouseButton::Left, 10f32, 10f32);
+        ui.mouse_button_up(MouseButton::Left, 200f32, 10f32);
+        assert!(run(&mut ui, &mut manager, &layout).is_empty());
+    }
+
+    #[test]
+    fn test_hover() {
+        let mut manager = EntityManager::new();
+        let id = add_widget(&mut manager, MENU, button("Play"));
+        manager.update();
+        let layout = [(id, Rect::new(0f32, 0f32, 100f32, 40f32))];
+        let mut ui = Ui::default();
+
+        ui.mouse_motion(50f32, 20f32);
+        run(&mut ui, &mut manager, &layout);
+        assert!(
+            manager
+                .get_entity(id)
+                .unwrap()
+                .get_component::<Widget>()
+                .unwrap()
+                .hovered
+        );
+
+        ui.mouse_motion(150f32, 20f32);
+        run(&mut ui, &mut manager, &layout);
+        assert!(
+            !manager
+                .get_entity(id)
+                .unwrap()
+                .get_component::<Widget>()
+                .unwrap()
+                .hovered
+        );
+    }
+
+    #[test]
+    fn test_focus_skips_disabled() {
+        let mut manager = EntityManager::new();
+        let first = add_widget(&mut manager, MENU, button("A"));
+        let disabled = add_widget(&mut manager, MENU, button("B"));
+        let last = add_widget(&mut manager, MENU, button("C"));
+        manager.update();
+        manager
+            .get_entity(disabled)
+            .unwrap()
+            .get_component_mut::<Widget>()
+            .unwrap()
+            .disabled = true;
+        let layout = [
+            (first, Rect::new(0f32, 0f32, 100f32, 40f32)),
+            (disabled, Rect::new(0f32, 50f32, 100f32, 40f32)),
+            (last, Rect::new(0f32, 100f32, 100f32, 40f32)),
+        ];
+        let mut ui = Ui::default();
+
+        ui.key_down(KeyCode::Tab, KeyMods::NONE, false);
+        ui.key_down(KeyCode::Tab, KeyMods::NONE, false);
+        run(&mut ui, &mut manager, &layout);
+        assert_eq!(ui.focus, Some(last));
+
+        ui.key_down(KeyCode::Down, KeyMods::NONE, false);
+        ui.key_down(KeyCode::Space, KeyMods::NONE, false);
+        assert_eq!(
+            run(&mut ui, &mut manager, &layout),
+            vec![UiEvent::Clicked(first)]
+        );
+
+        ui.mouse_button_down(MouseButton::Left, 10f32, 60f32);
+        ui.mouse_button_up(MouseButton::Left, 10f32, 60f32);
+        assert!(run(&mut ui, &mut manager, &layout).is_empty());
+        assert_eq!(ui.focus, None);
+    }
+
+    #[test]
+    fn test_checkbox_toggles() {
+        let mut manager = EntityManager::new();
+        let checkbox = Checkbox {
+            label: "Record".to_string(),
+            size: 20f32,
+            checked: false,
+        };
+        let id = add_widget(&mut manager, MENU, checkbox);
+        manager.update();
+        let layout = [(id, Rect::new(0f32, 0f32, 100f32, 40f32))];
+        let mut ui = Ui::default();
+        ui.set_focus(Some(id));
+
+        ui.key_down(KeyCode::Space, KeyMods::NONE, false);
+        ui.key_down(KeyCode::Space, KeyMods::NONE, false);
+        assert_eq!(
+            run(&mut ui, &mut manager, &layout),
+            vec![UiEvent::Toggled(id, true), UiEvent::Toggled(id, false)]
+        );
+    }
+
+    #[test]
+    fn test_text_input() {
+        let mut manager = EntityManager::new();
+        let input = TextInput {
+            text: String::new(),
+            placeholder: "Seed".to_string(),
+            size: 20f32,
+            max_len: 2,
+        };
+        let id = add_widget(&mut manager, MENU, input);
+        manager.update();
+        let layout = [(id, Rect::new(0f32, 0f32, 100f32, 40f32))];
+        let mut ui = Ui::default();
+        ui.set_focus(Some(id));
+
+        for character in ['4', '\u{8}', '3', '0', '0'] {
+            ui.text_input(character);
+        }
+        ui.key_down(KeyCode::Back, KeyMods::NONE, false);
+        ui.key_down(KeyCode::Return, KeyMods::NONE, false);
+
+        assert_eq!(
+            run(&mut ui, &mut manager, &layout),
+            vec![
+                UiEvent::TextChanged(id, "4".to_string()),
+                UiEvent::TextChanged(id, "43".to_string()),
+                UiEvent::TextChanged(id, "4".to_string()),
+                UiEvent::Submitted(id, "4".to_string()),
+            ]
+        );
+    }
+}
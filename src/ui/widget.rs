@@ -0,0 +1,263 @@
+use ecs::entity::Entity;
+use ggez::graphics::{Color, DrawMode, Font, MeshBuilder, PxScale, Rect, StrokeOptions, Text};
+use ggez::{Context, GameResult};
+
+const PADDING: f32 = 8f32;
+const SLIDER_WIDTH: f32 = 300f32;
+const TEXT_INPUT_WIDTH: f32 = 300f32;
+const DISABLED_COLOR: Color = Color::new(0.6, 0.6, 0.6, 1.0);
+const HOVERED_COLOR: Color = Color::new(0.9, 0.9, 0.9, 1.0);
+const PRESSED_COLOR: Color = Color::new(0.75, 0.75, 0.75, 1.0);
+
+/// Interaction state every widget carries next to its kind component.
+#[derive(Debug, Default)]
+pub struct Widget {
+    pub hovered: bool,
+    pub pressed: bool,
+    pub focused: bool,
+    pub disabled: bool,
+}
+
+pub struct Button {
+    pub title: String,
+    pub size: f32,
+}
+
+pub struct Label {
+    pub text: String,
+    pub size: f32,
+}
+
+pub struct Checkbox {
+    pub label: String,
+    pub size: f32,
+    pub checked: bool,
+}
+
+pub struct Slider {
+    pub label: String,
+    pub size: f32,
+    pub value: f32,
+    pub min: f32,
+    pub max: f32,
+    pub step: f32,
+}
+
+impl Slider {
+    /// Clamps and snaps `value` to the slider range, returns whether it changed.
+    pub fn set_value(&mut self, value: f32) -> bool {
+        let mut value = value.clamp(self.min, self.max);
+        if self.step > 0f32 {
+            value = self.min + ((value - self.min) / self.step).round() * self.step;
+            value = value.min(self.max);
+        }
+        let changed = value != self.value;
+        self.value = value;
+        changed
+    }
+
+    pub fn value_at(&self, rect: Rect, x: f32) -> f32 {
+        let t = ((x - rect.x) / rect.w).clamp(0f32, 1f32);
+        self.min + t * (self.max - self.min)
+    }
+
+    fn fraction(&self) -> f32 {
+        if self.max > self.min {
+            (self.value - self.min) / (self.max - self.min)
+        } else {
+            0f32
+        }
+    }
+}
+
+pub struct TextInput {
+    pub text: String,
+    pub placeholder: String,
+    pub size: f32,
+    pub max_len: usize,
+}
+
+/// Whether the widget reacts to the pointer and can take keyboard focus.
+pub fn is_interactive(entity: &Entity) -> bool {
+    entity.has_component::<Button>()
+        || entity.has_component::<Checkbox>()
+        || entity.has_component::<Slider>()
+        || entity.has_component::<TextInput>()
+}
+
+fn text(content: impl Into<String>, size: f32) -> Text {
+    let mut text = Text::new(content.into());
+    text.set_font(Font::default(), PxScale::from(size));
+    text
+}
+
+fn slider_text(slider: &Slider) -> Text {
+    text(
+        format!("{}: {:.2}", slider.label, slider.value),
+        slider.size,
+    )
+}
+
+/// Width and height the widget takes up on screen.
+pub fn measure(entity: &Entity, ctx: &Context) -> Option<(f32, f32)> {
+    if let Some(button) = entity.get_component::<Button>() {
+        let text = text(button.title.clone(), button.size);
+        return Some((
+            text.width(ctx) + 2f32 * PADDING,
+            text.height(ctx) + 2f32 * PADDING,
+        ));
+    }
+    if let Some(label) = entity.get_component::<Label>() {
+        let text = text(label.text.clone(), label.size);
+        return Some((text.width(ctx), text.height(ctx)));
+    }
+    if let Some(checkbox) = entity.get_component::<Checkbox>() {
+        let text = text(checkbox.label.clone(), checkbox.size);
+        return Some((
+            checkbox.size + PADDING + text.width(ctx),
+            checkbox.size.max(text.height(ctx)),
+        ));
+    }
+    if let Some(slider) = entity.get_component::<Slider>() {
+        let text = slider_text(slider);
+        return Some((
+            SLIDER_WIDTH.max(text.width(ctx)),
+            text.height(ctx) + slider.size / 2f32,
+        ));
+    }
+    if let Some(input) = entity.get_component::<TextInput>() {
+        let text = text("Ay", input.size);
+        return Some((TEXT_INPUT_WIDTH, text.height(ctx) + 2f32 * PADDING));
+    }
+    None
+}
+
+pub fn draw(entity: &Entity, rect: Rect, ctx: &mut Context) -> GameResult<()> {
+    let state = match entity.get_component::<Widget>() {
+        Some(state) => state,
+        None => return Ok(()),
+    };
+    let color = if state.disabled {
+        DISABLED_COLOR
+    } else {
+        Color::BLACK
+    };
+    let border = if state.focused { 3f32 } else { 1f32 };
+
+    let mut mesh = MeshBuilder::new();
+    if state.pressed {
+        mesh.rectangle(DrawMode::fill(), rect, PRESSED_COLOR)?;
+    } else if state.hovered {
+        mesh.rectangle(DrawMode::fill(), rect, HOVERED_COLOR)?;
+    }
+
+    let (content, position) = if let Some(button) = entity.get_component::<Button>() {
+        mesh.rectangle(DrawMode::stroke(border), rect, color)?;
+        (
+            text(button.title.clone(), button.size),
+            [rect.x + PADDING, rect.y + PADDING],
+        )
+    } else if let Some(label) = entity.get_component::<Label>() {
+        (text(label.text.clone(), label.size), [rect.x, rect.y])
+    } else if let Some(checkbox) = entity.get_component::<Checkbox>() {
+        let size = checkbox.size;
+        let square = Rect::new(rect.x, rect.y + (rect.h - size) / 2f32, size, size);
+        mesh.rectangle(DrawMode::stroke(border), square, color)?;
+        if checkbox.checked {
+            let inner = Rect::new(
+                square.x + size / 4f32,
+                square.y + size / 4f32,
+                size / 2f32,
+                size / 2f32,
+            );
+            mesh.rectangle(DrawMode::fill(), inner, color)?;
+        }
+        (
+            text(checkbox.label.clone(), size),
+            [rect.x + size + PADDING, rect.y],
+        )
+    } else if let Some(slider) = entity.get_component::<Slider>() {
+        let track_y = rect.y + rect.h - slider.size / 4f32;
+        mesh.line(
+            &[[rect.x, track_y], [rect.x + rect.w, track_y]],
+            border + 1f32,
+            color,
+        )?;
+        let knob = [rect.x + slider.fraction() * rect.w, track_y];
+        mesh.circle(DrawMode::fill(), knob, slider.size / 4f32, 0.5, color)?;
+        (slider_text(slider), [rect.x, rect.y])
+    } else if let Some(input) = entity.get_component::<TextInput>() {
+        mesh.rectangle(DrawMode::Stroke(StrokeOptions::DEFAULT), rect, color)?;
+        if state.focused {
+            mesh.rectangle(DrawMode::stroke(border), rect, color)?;
+        }
+        let content = if input.text.is_empty() && !state.focused {
+            input.placeholder.clone()
+        } else if state.focused {
+            format!("{}|", input.text)
+        } else {
+            input.text.clone()
+        };
+        (
+            text(content, input.size),
+            [rect.x + PADDING, rect.y + PADDING],
+        )
+    } else {
+        return Ok(());
+    };
+
+    // MeshBuilder refuses to build an empty mesh.
+    if state.pressed || state.hovered || !entity.has_component::<Label>() {
+        let mesh = mesh.build(ctx)?;
+        ggez::graphics::draw(ctx, &mesh, ([0f32, 0f32], Color::WHITE))?;
+    }
+
+    let placeholder = entity
+        .get_component::<TextInput>()
+        .is_some_and(|input| input.text.is_empty() && !state.focused);
+    let text_color = if placeholder { DISABLED_COLOR } else { color };
+    ggez::graphics::draw(ctx, &content, (position, text_color))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Slider;
+    use ggez::graphics::Rect;
+
+    fn slider() -> Slider {
+        Slider {
+            label: "Volume".to_string(),
+            size: 20f32,
+            value: 0f32,
+            min: 0f32,
+            max: 1f32,
+            step: 0.25,
+        }
+    }
+
+    #[test]
+    fn test_slider_snaps_to_step() {
+        let mut slider = slider();
+        assert!(slider.set_value(0.4));
+        assert_eq!(slider.value, 0.5);
+        assert!(!slider.set_value(0.55));
+    }
+
+    #[test]
+    fn test_slider_clamps() {
+        let mut slider = slider();
+        slider.set_value(7f32);
+        assert_eq!(slider.value, 1f32);
+        slider.set_value(-2f32);
+        assert_eq!(slider.value, 0f32);
+    }
+
+    #[test]
+    fn test_slider_value_at() {
+        let slider = slider();
+        let rect = Rect::new(100f32, 0f32, 200f32, 20f32);
+        assert_eq!(slider.value_at(rect, 200f32), 0.5);
+        assert_eq!(slider.value_at(rect, 0f32), 0f32);
+        assert_eq!(slider.value_at(rect, 400f32), 1f32);
+    }
+}